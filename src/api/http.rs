@@ -0,0 +1,244 @@
+use std::error;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, TcpListener, TcpStream};
+use std::thread;
+
+use super::{handle, Request, Response, READ_TIMEOUT};
+use crate::config::Config;
+
+/// Default address of the REST API
+pub const DEFAULT_LISTEN: &str = "127.0.0.1:9862";
+
+/// Status page listing all services
+const INDEX_HTML: &str = include_str!("index.html");
+
+/// Serve the REST API and the status page on `addr` until killed
+pub fn serve(config: Config, addr: &str) -> Result<String, Box<dyn error::Error>> {
+    let listener = TcpListener::bind(addr)?;
+    eprintln!("Serving on http://{}/", addr);
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(_) => continue,
+        };
+
+        let config = config.clone();
+        thread::spawn(move || {
+            if let Err(err) = serve_client(&config, stream) {
+                eprintln!("warning: {}", err);
+            }
+        });
+    }
+
+    Ok(String::new())
+}
+
+fn serve_client(config: &Config, mut stream: TcpStream) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_owned();
+    let path = parts.next().unwrap_or_default().to_owned();
+
+    let mut token = None;
+    let mut origin = None;
+    let mut host = None;
+    let mut content_length = 0;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+
+        let (name, value) = match line.split_once(':') {
+            Some((name, value)) => (name.trim().to_lowercase(), value.trim().to_owned()),
+            None => continue,
+        };
+
+        match name.as_str() {
+            "authorization" => token = value.strip_prefix("Bearer ").map(|t| t.to_owned()),
+            "content-length" => content_length = value.parse().unwrap_or(0),
+            "origin" => origin = Some(value),
+            "host" => host = Some(value),
+            _ => (),
+        }
+    }
+
+    // Request bodies aren't used
+    io::copy(&mut reader.by_ref().take(content_length), &mut io::sink())?;
+
+    if method == "GET" && path == "/" {
+        return write_response(&mut stream, "200 OK", "text/html", INDEX_HTML);
+    }
+
+    // A website resolving its own name to this host could talk to
+    // the API as same origin otherwise (DNS rebinding)
+    if !host.as_deref().map_or(true, is_local_host) {
+        return write_json(
+            &mut stream,
+            "403 Forbidden",
+            &Response::error("Host has to be an IP address or localhost"),
+        );
+    }
+
+    // Other websites may send requests through the browser of the user
+    let origin_host = origin
+        .as_deref()
+        .map(|o| o.split_once("://").map_or(o, |(_, h)| h));
+    if origin_host.is_some() && origin_host != host.as_deref() {
+        return write_json(
+            &mut stream,
+            "403 Forbidden",
+            &Response::error("Foreign origin"),
+        );
+    }
+
+    if !config.api_token.is_empty()
+        && !token.is_some_and(|token| constant_time_eq(&token, &config.api_token))
+    {
+        return write_json(
+            &mut stream,
+            "401 Unauthorized",
+            &Response::error("Invalid token"),
+        );
+    }
+
+    let request = match route(&method, &path) {
+        Some(request) => request,
+        None => {
+            return write_json(&mut stream, "404 Not Found", &Response::error("Not found"));
+        }
+    };
+
+    // Every local user could change services otherwise
+    if method != "GET" && config.api_token.is_empty() {
+        return write_json(
+            &mut stream,
+            "403 Forbidden",
+            &Response::error("Controlling services requires api_token to be set"),
+        );
+    }
+
+    let response = handle(config, request);
+    let status = if response.ok {
+        "200 OK"
+    } else {
+        "400 Bad Request"
+    };
+    write_json(&mut stream, status, &response)
+}
+
+/// Whether `host`, a Host header, names this machine without using DNS
+fn is_local_host(host: &str) -> bool {
+    // Strip the port, IPv6 addresses are enclosed in brackets
+    let name = match host.strip_prefix('[') {
+        Some(rest) => rest.split(']').next().unwrap_or_default(),
+        None => host.split(':').next().unwrap_or_default(),
+    };
+
+    name == "localhost" || name.parse::<IpAddr>().is_ok()
+}
+
+/// Compare `a` and `b` in a time independent of their content
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// The API request of a REST endpoint
+fn route(method: &str, path: &str) -> Option<Request> {
+    let path = path.split('?').next().unwrap_or_default();
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+    match (method, segments.as_slice()) {
+        ("GET", ["services"]) => Some(Request::List),
+        ("GET", ["services", name]) => Some(Request::Status {
+            service: name.to_string(),
+        }),
+        ("POST", ["services", name, command @ ("start" | "stop" | "restart")]) => {
+            Some(Request::Control {
+                service: name.to_string(),
+                command: command.to_string(),
+            })
+        }
+        _ => None,
+    }
+}
+
+fn write_json(stream: &mut TcpStream, status: &str, response: &Response) -> io::Result<()> {
+    let body = serde_json::to_string(response)?;
+    write_response(stream, status, "application/json", &body)
+}
+
+fn write_response(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &str,
+) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.0 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn routes_service_endpoints() {
+        assert_eq!(route("GET", "/services"), Some(Request::List));
+        assert_eq!(route("GET", "/services/?all"), Some(Request::List));
+        assert_eq!(
+            route("GET", "/services/sshd"),
+            Some(Request::Status {
+                service: "sshd".to_owned()
+            })
+        );
+        assert_eq!(
+            route("POST", "/services/sshd/restart"),
+            Some(Request::Control {
+                service: "sshd".to_owned(),
+                command: "restart".to_owned()
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_routes() {
+        assert_eq!(route("GET", "/"), None);
+        assert_eq!(route("POST", "/services"), None);
+        assert_eq!(route("GET", "/services/sshd/start"), None);
+        assert_eq!(route("POST", "/services/sshd/kill"), None);
+        assert_eq!(route("DELETE", "/services/sshd"), None);
+    }
+
+    #[test]
+    fn accepts_only_local_hosts() {
+        assert!(is_local_host("localhost:8080"));
+        assert!(is_local_host("127.0.0.1:8080"));
+        assert!(is_local_host("[::1]:8080"));
+        assert!(is_local_host("10.0.0.2"));
+        assert!(!is_local_host("evil.example:8080"));
+        assert!(!is_local_host(""));
+    }
+
+    #[test]
+    fn compares_tokens() {
+        assert!(constant_time_eq("secret", "secret"));
+        assert!(!constant_time_eq("secret", "secreT"));
+        assert!(!constant_time_eq("secret", "secrets"));
+    }
+}
@@ -0,0 +1,139 @@
+use serde_derive::{Deserialize, Serialize};
+use serde_json::Value;
+
+use std::error;
+use std::time::Duration;
+
+use crate::config::Config;
+use crate::sv::cmdtype::SvCommandType;
+use crate::sv::info::ServiceInfo;
+use crate::sv::service::{Service, ServiceSrc};
+
+pub mod http;
+pub mod socket;
+
+/// Time a client may stay silent before its connection gets closed
+pub const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Commands which can be sent to services through the API
+pub const CONTROL_COMMANDS: [&str; 11] = [
+    "start",
+    "stop",
+    "restart",
+    "once",
+    "pause",
+    "continue",
+    "term",
+    "hup",
+    "alarm",
+    "interrupt",
+    "kill",
+];
+
+/// Callers allowed to use the API, besides root
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ApiAllow {
+    #[serde(default)]
+    pub uids: Vec<u32>,
+    #[serde(default)]
+    pub gids: Vec<u32>,
+}
+
+impl ApiAllow {
+    /// Whether the user `uid`, member of `gids`, may use the API
+    pub fn allows(&self, uid: u32, gids: &[u32]) -> bool {
+        uid == 0 || self.uids.contains(&uid) || gids.iter().any(|gid| self.gids.contains(gid))
+    }
+}
+
+/// A request to the API, eg. {"action": "control", "service": "sshd", "command": "restart"}
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "lowercase")]
+pub enum Request {
+    List,
+    Status { service: String },
+    Control { service: String, command: String },
+    Enable { service: String },
+    Disable { service: String },
+}
+
+/// The answer to a `Request`. `result` is set on success, `error` otherwise
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Response {
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Response {
+    pub fn error<S: ToString>(err: S) -> Response {
+        Response {
+            ok: false,
+            result: None,
+            error: Some(err.to_string()),
+        }
+    }
+}
+
+/// Handle a single request
+pub fn handle(config: &Config, request: Request) -> Response {
+    match run(config, request) {
+        Ok(result) => Response {
+            ok: true,
+            result: Some(result),
+            error: None,
+        },
+        Err(err) => Response::error(err),
+    }
+}
+
+fn run(config: &Config, request: Request) -> Result<Value, Box<dyn error::Error>> {
+    let service = match request {
+        Request::List => {
+            let mut infos = Vec::new();
+            for service in Service::get_all_services(config.clone())? {
+                if let Some(status) = service.try_read_status()? {
+                    infos.push(ServiceInfo::new(&service, &status));
+                }
+            }
+
+            return Ok(serde_json::to_value(infos)?);
+        }
+        Request::Status { ref service }
+        | Request::Control { ref service, .. }
+        | Request::Enable { ref service }
+        | Request::Disable { ref service } => {
+            Service::new(service.clone(), config.clone(), ServiceSrc::RunSvDir)?
+        }
+    };
+
+    if !service.exists() {
+        return Err(format!("Service '{}' not found", service.uri).into());
+    }
+
+    let command = match request {
+        Request::Status { .. } => {
+            // Disabled services have no status
+            let mut service = service;
+            if !service.is_enabled() {
+                service.src = ServiceSrc::ServiceDir;
+            }
+
+            let status = service.read_status()?;
+            return Ok(serde_json::to_value(ServiceInfo::new(&service, &status))?);
+        }
+        Request::Control { ref command, .. } => {
+            if !CONTROL_COMMANDS.contains(&command.as_str()) {
+                return Err(format!("Unknown command '{}'", command).into());
+            }
+            command.as_str()
+        }
+        Request::Enable { .. } => "enable",
+        _ => "disable",
+    };
+
+    let output = service.run(SvCommandType::from(command))?;
+    Ok(Value::String(output.trim_end().to_owned()))
+}
@@ -0,0 +1,109 @@
+use std::error;
+use std::fs;
+use std::io::{self, BufRead, BufReader, ErrorKind, Write};
+use std::mem;
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::io::AsRawFd;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
+use std::thread;
+
+use super::{handle, Request, Response, READ_TIMEOUT};
+use crate::config::Config;
+use crate::users;
+
+/// Serve the API on the unix socket at `path` until killed. Every
+/// user may connect, callers get authorized by their credentials
+pub fn serve(config: Config, path: &Path) -> Result<String, Box<dyn error::Error>> {
+    // Left behind by a previous daemon
+    if path.exists() {
+        fs::remove_file(path)?;
+    }
+
+    let listener = UnixListener::bind(path)?;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o666))?;
+
+    eprintln!("Listening on {}", path.display());
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                eprintln!("warning: {}", err);
+                continue;
+            }
+        };
+
+        let config = config.clone();
+        thread::spawn(move || {
+            if let Err(err) = serve_client(&config, stream) {
+                eprintln!("warning: {}", err);
+            }
+        });
+    }
+
+    Ok(String::new())
+}
+
+/// Answer each line of JSON sent by the client with a line of JSON
+fn serve_client(config: &Config, stream: UnixStream) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+
+    // SO_PEERCRED only contains the primary group
+    let (uid, gid) = peer_credentials(&stream)?;
+    let allowed = config.api_allow.allows(uid, &users::group_ids(uid, gid));
+
+    // Changes get logged as made by the client, not the daemon
+    let mut config = config.clone();
+    config.caller = Some((uid, gid));
+
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = match line {
+            Ok(line) => line,
+            // Idle for too long
+            Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => break,
+            Err(err) => return Err(err),
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = if !allowed {
+            Response::error(format!("uid {} gid {} is not allowed", uid, gid))
+        } else {
+            match serde_json::from_str::<Request>(&line) {
+                Ok(request) => handle(&config, request),
+                Err(err) => Response::error(format!("Invalid request: {}", err)),
+            }
+        };
+
+        let mut out = serde_json::to_string(&response)?;
+        out.push('\n');
+        writer.write_all(out.as_bytes())?;
+    }
+
+    Ok(())
+}
+
+/// Uid and gid of the process connected to `stream`
+fn peer_credentials(stream: &UnixStream) -> io::Result<(u32, u32)> {
+    let mut cred: libc::ucred = unsafe { mem::zeroed() };
+    let mut len = mem::size_of::<libc::ucred>() as libc::socklen_t;
+
+    let res = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            &mut cred as *mut libc::ucred as *mut libc::c_void,
+            &mut len,
+        )
+    };
+
+    if res != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok((cred.uid, cred.gid))
+}
@@ -1,5 +1,8 @@
 use clap::{crate_version, App, AppSettings, Arg};
 
+use crate::api;
+use crate::exporter::DEFAULT_LISTEN;
+
 fn get_base_app_struct<S: AsRef<str>>(name: S, about: &'static str) -> App<'static> {
     App::new(name.as_ref().to_string())
         .setting(AppSettings::TrailingVarArg)
@@ -19,6 +22,16 @@ fn get_service_subcommand<S: AsRef<str>>(name: S, about: &'static str) -> App<'s
 }
 
 pub fn get_cli() -> App<'static> {
+    get_public_cli().subcommand(
+        App::new("__complete")
+            .setting(AppSettings::Hidden)
+            .about("Print the services completing the argument of a subcommand")
+            .arg(Arg::new("subcommand").required(true)),
+    )
+}
+
+/// The cli without internal helpers, eg. for generating shell completions
+pub fn get_public_cli() -> App<'static> {
     get_base_app_struct(
         "rsv",
         "A tool to maintain runit services like systemd services",
@@ -29,17 +42,99 @@ pub fn get_cli() -> App<'static> {
     .arg(
         Arg::new("generator")
             .long("generate")
-            .help("Generate completion scripts for a given type of shell")
-            .possible_values(&["bash", "elvish", "fish", "zsh"]),
+            .help("Generate completion scripts for a given type of shell, or man pages")
+            .possible_values(["bash", "elvish", "fish", "zsh", "man"]),
+    )
+    .arg(
+        Arg::new("out-dir")
+            .long("out-dir")
+            .help("Write all man pages into the given dir instead of printing rsv(8)")
+            .requires("generator")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::new("verbose")
+            .short('v')
+            .long("verbose")
+            .help("Print the config and where its values come from")
+            .global(true),
+    )
+    .arg(
+        Arg::new("user")
+            .long("user")
+            .help("Manage the services of the current user")
+            .global(true),
+    )
+    .arg(
+        Arg::new("config")
+            .long("config")
+            .help("Use the given config file")
+            .global(true)
+            .takes_value(true),
+    )
+    .arg(
+        Arg::new("env")
+            .long("env")
+            .help("Set an environment variable, used when rerunning as root")
+            .global(true)
+            .hide(true)
+            .takes_value(true)
+            .multiple_occurrences(true),
+    )
+    .arg(
+        Arg::new("dry-run")
+            .long("dry-run")
+            .help("Only print what would be changed")
+            .global(true),
+    )
+    .arg(
+        Arg::new("save-config")
+            .long("save-config")
+            .help("Save the effective config for later runs")
+            .global(true),
+    )
+    .arg(
+        Arg::new("svdir")
+            .long("svdir")
+            .help("Use the runsvdir with the given path or name")
+            .global(true)
+            .takes_value(true),
     )
-    .arg(Arg::new("verbose").short('v').long("verbose").global(true))
     .arg(
         Arg::new("timeout")
             .short('t')
             .long("timeout")
+            .help("Seconds to wait for a command to take effect")
             .global(true)
             .takes_value(true),
     )
+    .arg(
+        Arg::new("kill")
+            .long("kill")
+            .help("Kill the service if it doesn't stop within the timeout")
+            .global(true)
+            .conflicts_with("no-kill"),
+    )
+    .arg(
+        Arg::new("no-kill")
+            .long("no-kill")
+            .help("Don't kill the service if it doesn't stop within the timeout")
+            .global(true),
+    )
+    .arg(
+        Arg::new("format")
+            .long("format")
+            .help("Output format")
+            .global(true)
+            .possible_values(["auto", "sv", "table", "json"]),
+    )
+    .arg(
+        Arg::new("color")
+            .long("color")
+            .help("When to use colors")
+            .global(true)
+            .possible_values(["auto", "always", "never"]),
+    )
     .subcommand(get_service_subcommand("enable", "Enable a service"))
     .subcommand(get_service_subcommand("disable", "Disable a service"))
     .subcommand(get_service_subcommand("start", "Start a service"))
@@ -49,6 +144,14 @@ pub fn get_cli() -> App<'static> {
         "status",
         "Get the status of a service",
     ))
+    .subcommand(get_service_subcommand(
+        "cat",
+        "Print the files defining a service",
+    ))
+    .subcommand(get_service_subcommand(
+        "show",
+        "Show the properties of a service",
+    ))
     .subcommand(get_service_subcommand(
         "once",
         "Start if service is not running. Do not restart if it stops",
@@ -81,13 +184,237 @@ pub fn get_cli() -> App<'static> {
         "kill",
         "Send SIGKILL if the service is running",
     ))
+    .subcommand(
+        get_base_app_struct("config", "Inspect and change the config")
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .subcommand(get_base_app_struct(
+                "show",
+                "Show all values and where they come from",
+            ))
+            .subcommand(
+                get_base_app_struct("get", "Print a single value")
+                    .arg(Arg::new("key").help("Key of the value").required(true)),
+            )
+            .subcommand(
+                get_base_app_struct("set", "Change a value and save the config")
+                    .arg(Arg::new("key").help("Key of the value").required(true))
+                    .arg(Arg::new("value").help("The new value").required(true)),
+            )
+            .subcommand(get_base_app_struct(
+                "validate",
+                "Check the config for problems",
+            ))
+            .subcommand(get_base_app_struct(
+                "reset",
+                "Reset the saved config to the defaults",
+            ))
+            .subcommand(get_base_app_struct(
+                "path",
+                "Print the path of the saved config",
+            )),
+    )
+    .subcommand(
+        // Without trailing var args, so the group may follow the service
+        App::new("grant")
+            .about("Allow a group to control a service")
+            .setting(AppSettings::ArgRequiredElseHelp)
+            .arg(
+                Arg::new("service")
+                    .help("The name of the service")
+                    .required_unless_present("reapply"),
+            )
+            .arg(
+                Arg::new("group")
+                    .long("group")
+                    .short('g')
+                    .takes_value(true)
+                    .value_name("group")
+                    .help("The group allowed to control the service")
+                    .required_unless_present("reapply"),
+            )
+            .arg(
+                Arg::new("reapply")
+                    .long("reapply")
+                    .conflicts_with_all(&["service", "group"])
+                    .help("Apply all saved grants again, eg. after runsv recreated the files"),
+            ),
+    )
+    .subcommand(get_service_subcommand(
+        "revoke",
+        "Revoke the group access to a service",
+    ))
+    .subcommand(get_base_app_struct(
+        "export",
+        "Print the enabled services as YAML",
+    ))
+    .subcommand(
+        get_base_app_struct("import", "Enable and disable services to match an export")
+            .setting(AppSettings::ArgRequiredElseHelp)
+            .arg(
+                Arg::new("file")
+                    .help("The file written by export")
+                    .required(true)
+                    .takes_value(true),
+            ),
+    )
+    .subcommand(
+        get_base_app_struct("apply", "Converge services to a desired state")
+            .setting(AppSettings::ArgRequiredElseHelp)
+            .arg(
+                Arg::new("file")
+                    .help("YAML file listing services and their desired state")
+                    .required(true)
+                    .takes_value(true),
+            ),
+    )
+    .subcommand(
+        get_base_app_struct("health", "Run the health checks of services").arg(
+            Arg::new("service")
+                .help("The services to check. All enabled ones if omitted")
+                .multiple_values(true),
+        ),
+    )
+    .subcommand(
+        get_base_app_struct("exporter", "Serve prometheus metrics of all services").arg(
+            Arg::new("listen")
+                .long("listen")
+                .short('l')
+                .takes_value(true)
+                .value_name("address")
+                .default_value(DEFAULT_LISTEN)
+                .help("Address to serve /metrics on"),
+        ),
+    )
+    .subcommand(get_base_app_struct(
+        "monitor",
+        "Watch all services and run the configured hooks on state changes",
+    ))
+    .subcommand(
+        get_base_app_struct("daemon", "Serve the JSON API on a unix socket").arg(
+            Arg::new("socket")
+                .long("socket")
+                .takes_value(true)
+                .value_name("path")
+                .help("Listen on this socket instead of the configured one"),
+        ),
+    )
+    .subcommand(
+        get_base_app_struct("serve", "Serve a REST API and status page").arg(
+            Arg::new("listen")
+                .long("listen")
+                .short('l')
+                .takes_value(true)
+                .value_name("address")
+                .default_value(api::http::DEFAULT_LISTEN)
+                .help("Address to listen on"),
+        ),
+    )
+    .subcommand(get_base_app_struct(
+        "tui",
+        "Browse and control services interactively",
+    ))
+    .subcommand(get_base_app_struct(
+        "svdirs",
+        "List all running runsvdir instances",
+    ))
     .subcommand(
         get_base_app_struct("list", "List services")
-            .arg(Arg::new("all").long("all").short('a'))
-            .arg(Arg::new("all").long("all").short('a'))
-            .arg(Arg::new("up").long("up").short('u'))
-            .arg(Arg::new("down").long("down"))
-            .arg(Arg::new("enabled").long("enabled").short('e'))
-            .arg(Arg::new("disabled").long("disabled").short('d')),
+            .arg(
+                Arg::new("all-svdirs")
+                    .long("all-svdirs")
+                    .help("List the services of all running runsvdir instances"),
+            )
+            .arg(
+                Arg::new("all")
+                    .long("all")
+                    .short('a')
+                    .help("Include disabled services"),
+            )
+            .arg(
+                Arg::new("up")
+                    .long("up")
+                    .short('u')
+                    .help("Only running services, same as --state run")
+                    .conflicts_with_all(&["down", "state"]),
+            )
+            .arg(
+                Arg::new("down")
+                    .long("down")
+                    .help("Only services which are down, same as --state down")
+                    .conflicts_with("state"),
+            )
+            .arg(
+                Arg::new("enabled")
+                    .long("enabled")
+                    .short('e')
+                    .help("Only enabled services")
+                    .conflicts_with("disabled"),
+            )
+            .arg(
+                Arg::new("disabled")
+                    .long("disabled")
+                    .short('d')
+                    .help("Only disabled services"),
+            )
+            .arg(
+                Arg::new("name")
+                    .long("name")
+                    .short('n')
+                    .takes_value(true)
+                    .value_name("regex")
+                    .help("Only services whose name matches the regex"),
+            )
+            .arg(
+                Arg::new("state")
+                    .long("state")
+                    .takes_value(true)
+                    .possible_values(["run", "down", "finish"])
+                    .help("Only services in the given state"),
+            )
+            .arg(
+                Arg::new("paused")
+                    .long("paused")
+                    .help("Only paused services"),
+            )
+            .arg(
+                Arg::new("want")
+                    .long("want")
+                    .takes_value(true)
+                    .possible_values(["up", "down"])
+                    .help("Only services runsv is about to bring up or down"),
+            )
+            .arg(
+                Arg::new("normally-down")
+                    .long("normally-down")
+                    .help("Only services with a down file"),
+            )
+            .arg(
+                Arg::new("min-uptime")
+                    .long("min-uptime")
+                    .takes_value(true)
+                    .value_name("duration")
+                    .help("Only services in their state for at least this long, eg. 5m"),
+            )
+            .arg(
+                Arg::new("max-uptime")
+                    .long("max-uptime")
+                    .takes_value(true)
+                    .value_name("duration")
+                    .help("Only services in their state for at most this long, eg. 30s"),
+            )
+            .arg(
+                Arg::new("sort")
+                    .long("sort")
+                    .takes_value(true)
+                    .possible_values(["name", "state", "uptime", "pid"])
+                    .default_value("name")
+                    .help("Order of the services"),
+            )
+            .arg(
+                Arg::new("reverse")
+                    .long("reverse")
+                    .short('r')
+                    .help("Reverse the order"),
+            ),
     )
 }
@@ -0,0 +1,92 @@
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::time::SystemTime;
+
+use crate::config::Config;
+use crate::privilege;
+use crate::sv::error::Error as SvError;
+use crate::users;
+
+/// Append a line describing a change made to `service` to the audit log.
+/// `control` are the bytes written to supervise/control, if any. Failing
+/// to write the log only prints a warning, the change was made already
+pub fn record(
+    config: &Config,
+    service: &str,
+    action: &str,
+    control: Option<&str>,
+    outcome: Result<&str, &SvError>,
+) {
+    if config.audit_log.is_empty() || config.dry_run {
+        return;
+    }
+
+    // The operation gets retried with root privileges
+    if let Err(err) = outcome {
+        if privilege::is_access_denied(err) {
+            return;
+        }
+    }
+
+    let outcome = match outcome {
+        Ok(outcome) => outcome.to_owned(),
+        Err(err) => format!("error: {}", err),
+    };
+
+    let line = format!(
+        "{} user={} command={:?} service={} action={} control={} outcome={:?}\n",
+        humantime::format_rfc3339_seconds(SystemTime::now()),
+        match config.caller {
+            Some((uid, gid)) => format!("{} uid={} gid={}", user_name(uid), uid, gid),
+            None => invoking_user(),
+        },
+        env::args().collect::<Vec<String>>().join(" "),
+        service,
+        action,
+        control.unwrap_or("-"),
+        outcome,
+    );
+
+    if let Err(err) = append(Path::new(&config.audit_log), &line) {
+        eprintln!(
+            "warning: can't write audit log {}: {}",
+            config.audit_log, err
+        );
+    }
+}
+
+/// The user who invoked rsv, before any privilege escalation
+fn invoking_user() -> String {
+    let uid = unsafe { libc::getuid() };
+
+    // Only sudo and doas running rsv as root can be trusted to set them
+    if unsafe { libc::geteuid() } != 0 {
+        return user_name(uid);
+    }
+
+    for var in ["SUDO_USER", "DOAS_USER"] {
+        if let Some(user) = env::var(var).ok().filter(|user| !user.is_empty()) {
+            return user;
+        }
+    }
+
+    user_name(uid)
+}
+
+fn user_name(uid: u32) -> String {
+    users::user_name(uid).unwrap_or_else(|| uid.to_string())
+}
+
+fn append(file: &Path, line: &str) -> std::io::Result<()> {
+    if let Some(dir) = file.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(file)?
+        .write_all(line.as_bytes())
+}
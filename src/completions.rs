@@ -0,0 +1,156 @@
+use std::error;
+
+use crate::config::Config;
+use crate::sv::service::{Service, ServiceSrc};
+
+/// Services offered to complete the service arg of a subcommand
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Candidates {
+    Disabled,
+    NotRunning,
+    Running,
+    Enabled,
+    All,
+}
+
+/// Subcommands taking services and what to complete them with
+const SUBCOMMANDS: [(&str, Candidates); 19] = [
+    ("enable", Candidates::Disabled),
+    ("start", Candidates::NotRunning),
+    ("once", Candidates::NotRunning),
+    ("stop", Candidates::Running),
+    ("restart", Candidates::Running),
+    ("pause", Candidates::Running),
+    ("continue", Candidates::Running),
+    ("term", Candidates::Running),
+    ("hup", Candidates::Running),
+    ("alarm", Candidates::Running),
+    ("interrupt", Candidates::Running),
+    ("kill", Candidates::Running),
+    ("disable", Candidates::Enabled),
+    ("status", Candidates::Enabled),
+    ("grant", Candidates::Enabled),
+    ("revoke", Candidates::Enabled),
+    ("health", Candidates::Enabled),
+    ("cat", Candidates::All),
+    ("show", Candidates::All),
+];
+
+/// Names of the services which make sense as argument of `subcommand`
+pub fn candidates(config: Config, subcommand: &str) -> Result<Vec<String>, Box<dyn error::Error>> {
+    let kind = match SUBCOMMANDS.iter().find(|(name, _)| *name == subcommand) {
+        Some((_, kind)) => *kind,
+        None => return Ok(Vec::new()),
+    };
+
+    let mut names = Vec::new();
+    for service in Service::get_all_services(config)? {
+        let enabled = service.src == ServiceSrc::RunSvDir;
+        // Completing must not fail, eg. on supervise dirs only root can read
+        let running =
+            enabled && matches!(service.try_read_status(), Ok(Some(status)) if status.is_running());
+
+        let matches = match kind {
+            Candidates::Disabled => !enabled,
+            Candidates::NotRunning => !running,
+            Candidates::Running => running,
+            Candidates::Enabled => enabled,
+            Candidates::All => true,
+        };
+
+        if matches {
+            names.push(service.uri);
+        }
+    }
+
+    names.sort();
+    Ok(names)
+}
+
+/// Make the script generated for `shell` complete service names
+pub fn add_dynamic(shell: &str, script: String) -> String {
+    match shell {
+        "bash" => script.replace(
+            "complete -F _rsv -o bashdefault -o default rsv",
+            BASH_DYNAMIC,
+        ),
+        "zsh" => match script.rfind("_rsv \"$@\"") {
+            Some(i) => format!("{}{}", &script[..i], ZSH_DYNAMIC),
+            None => script,
+        },
+        "fish" => script + &fish_dynamic(),
+        _ => script,
+    }
+}
+
+const BASH_DYNAMIC: &str = r#"_rsv_dynamic() {
+    local cur=${COMP_WORDS[COMP_CWORD]} sub="" extra="" i
+    for (( i = 1; i < COMP_CWORD; i++ )); do
+        case ${COMP_WORDS[i]} in
+            --user) extra=--user ;;
+            --config|--svdir|-t|--timeout|--format|--color|--env|-l|--listen|--socket|-g|--group|--out-dir) (( i++ )) ;;
+            -*) ;;
+            *) [[ -z $sub ]] && sub=${COMP_WORDS[i]} ;;
+        esac
+    done
+
+    if [[ -n $sub && $cur != -* ]]; then
+        local services
+        services=$(rsv $extra __complete "$sub" 2>/dev/null)
+        if [[ -n $services ]]; then
+            COMPREPLY=( $(compgen -W "$services" -- "$cur") )
+            return 0
+        fi
+    fi
+
+    _rsv "$@"
+}
+
+complete -F _rsv_dynamic -o bashdefault -o default rsv"#;
+
+const ZSH_DYNAMIC: &str = r#"_rsv_dynamic() {
+    local sub i
+    local -a extra services
+    for (( i = 2; i < CURRENT; i++ )); do
+        case ${words[i]} in
+            --user) extra=(--user) ;;
+            --config|--svdir|-t|--timeout|--format|--color|--env|-l|--listen|--socket|-g|--group|--out-dir) (( i++ )) ;;
+            -*) ;;
+            *) [[ -z $sub ]] && sub=${words[i]} ;;
+        esac
+    done
+
+    if [[ -n $sub && ${words[CURRENT]} != -* ]]; then
+        services=(${(f)"$(rsv $extra __complete $sub 2>/dev/null)"})
+        if (( ${#services} )); then
+            compadd -a services
+            return
+        fi
+    fi
+
+    _rsv "$@"
+}
+
+_rsv_dynamic "$@"
+"#;
+
+fn fish_dynamic() -> String {
+    let mut s = String::from(
+        r#"
+function __fish_rsv_services
+    set -l extra
+    contains -- --user (commandline -opc); and set extra --user
+    rsv $extra __complete $argv 2>/dev/null
+end
+"#,
+    );
+
+    for (subcommand, _) in SUBCOMMANDS.iter() {
+        s.push_str(&format!(
+            "complete -c rsv -n \"__fish_seen_subcommand_from {0}\" -f -a \"(__fish_rsv_services {0})\"\n",
+            subcommand
+        ));
+    }
+
+    s
+}
@@ -0,0 +1,101 @@
+use serde_derive::{Deserialize, Serialize};
+
+use std::fs;
+use std::path::Path;
+
+/// File containing additional distros, checked before the built-in ones
+pub const DISTRO_CONF_FILE: &str = "distros.conf";
+
+/// Pseudo os-release ID used inside of docker containers
+pub const DOCKER_ID: &str = "docker";
+
+/// Built-in runit based systems as (os-release IDs, service path, runsvdir)
+const KNOWN_DISTROS: [(&[&str], &str, &str); 9] = [
+    (&["artix"], "/etc/runit/sv/", "/run/runit/service/"),
+    (&["void"], "/etc/sv/", "/var/service/"),
+    (&["obarun"], "/etc/runit/sv/", "/run/runit/service/"),
+    (&["devuan", "antix"], "/etc/sv/", "/etc/service/"),
+    (&["gentoo", "funtoo"], "/etc/sv/", "/etc/service/"),
+    (&["alpine"], "/etc/sv/", "/etc/service/"),
+    (&["debian", "ubuntu"], "/etc/sv/", "/etc/service/"),
+    (&["slackware"], "/etc/sv/", "/var/service/"),
+    (&[DOCKER_ID], "/etc/sv/", "/etc/service/"),
+];
+
+/// Paths of a runit based system
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Distro {
+    /// os-release IDs of the system
+    pub ids: Vec<String>,
+    pub service_path: String,
+    pub runsv_dir: String,
+}
+
+impl Distro {
+    /// All distros known to rsv. Entries of `override_file` come first
+    pub fn all(override_file: &Path) -> Vec<Distro> {
+        let mut distros: Vec<Distro> = fs::read_to_string(override_file)
+            .ok()
+            .and_then(|content| match serde_yaml::from_str(&content) {
+                Ok(distros) => Some(distros),
+                Err(err) => {
+                    eprintln!("Ignoring {}: {}", override_file.display(), err);
+                    None
+                }
+            })
+            .unwrap_or_default();
+
+        distros.extend(
+            KNOWN_DISTROS
+                .iter()
+                .map(|(ids, service_path, runsv_dir)| Distro {
+                    ids: ids.iter().map(|id| id.to_string()).collect(),
+                    service_path: service_path.to_string(),
+                    runsv_dir: runsv_dir.to_string(),
+                }),
+        );
+
+        distros
+    }
+
+    fn matches(&self, id: &str) -> bool {
+        self.ids.iter().any(|i| i.eq_ignore_ascii_case(id))
+    }
+
+    /// Whether the service source path of the distro exists
+    fn is_installed(&self) -> bool {
+        Path::new(&self.service_path).is_dir()
+    }
+}
+
+/// Detect the distro of the running system using os-release
+/// ID and ID_LIKE. Only distros with existing paths are returned
+pub fn detect(override_file: &Path) -> Option<Distro> {
+    let distros = Distro::all(override_file);
+
+    release_ids().iter().find_map(|id| {
+        distros
+            .iter()
+            .find(|distro| distro.matches(id) && distro.is_installed())
+            .cloned()
+    })
+}
+
+/// IDs of the running system, most specific first
+fn release_ids() -> Vec<String> {
+    let mut ids = Vec::new();
+
+    if let Ok(release) = sys_info::linux_os_release() {
+        ids.extend(release.id.or(release.name));
+
+        if let Some(id_like) = release.id_like {
+            ids.extend(id_like.split_whitespace().map(|id| id.to_owned()));
+        }
+    }
+
+    if Path::new("/.dockerenv").exists() {
+        ids.push(DOCKER_ID.to_owned());
+    }
+
+    ids
+}
@@ -0,0 +1,162 @@
+use serde_yaml::{Mapping, Value};
+
+use std::collections::BTreeMap;
+use std::error;
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::Path;
+
+/// Source of a config value, ordered by precedence
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Layer {
+    Default,
+    Detected,
+    System,
+    User,
+    File,
+    Env,
+    Cli,
+    /// Asked for interactively since no layer set it
+    Prompt,
+}
+
+impl Layer {
+    pub fn value(&self) -> &str {
+        match self {
+            Layer::Default => "default",
+            Layer::Detected => "detected",
+            Layer::System => "system",
+            Layer::User => "user",
+            Layer::File => "file",
+            Layer::Env => "env",
+            Layer::Cli => "cli",
+            Layer::Prompt => "prompt",
+        }
+    }
+}
+
+/// Config values merged from multiple layers. Later
+/// layers override top level keys of earlier ones
+#[derive(Debug, Default)]
+pub struct Layers {
+    values: Mapping,
+    pub origins: BTreeMap<String, Layer>,
+}
+
+impl Layers {
+    /// Set a single value
+    pub fn set<V: Into<Value>>(&mut self, key: &str, value: V, layer: Layer) {
+        self.values.insert(Value::from(key), value.into());
+        self.origins.insert(key.to_owned(), layer);
+    }
+
+    /// Merge all values of `mapping`
+    pub fn merge(&mut self, mapping: Mapping, layer: Layer) {
+        for (key, value) in mapping {
+            if let Some(key) = key.as_str() {
+                self.set(key, value, layer);
+            }
+        }
+    }
+
+    /// Merge the values of a config file. Missing files are ignored
+    pub fn merge_file(&mut self, file: &Path, layer: Layer) -> Result<(), Box<dyn error::Error>> {
+        let content = match fs::read_to_string(file) {
+            Ok(content) => content,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(()),
+            // Keep the kind, so unreadable configs can be retried as root
+            Err(err) => {
+                let msg = format!("{}: {}", file.display(), err);
+                return Err(io::Error::new(err.kind(), msg).into());
+            }
+        };
+
+        match serde_yaml::from_str(&content) {
+            Ok(Value::Mapping(mapping)) => self.merge(mapping, layer),
+            Ok(Value::Null) => (),
+            Ok(_) => return Err(format!("{}: not a mapping", file.display()).into()),
+            Err(err) => return Err(format!("{}: {}", file.display(), err).into()),
+        }
+
+        Ok(())
+    }
+
+    pub fn into_value(self) -> Value {
+        Value::Mapping(self.values)
+    }
+}
+
+/// Format a config value for humans
+pub fn value_string(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => s.clone(),
+        _ => serde_yaml::to_string(value)
+            .map(|s| s.trim_start_matches("---").trim().replace('\n', ", "))
+            .unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+    use std::process;
+
+    fn mapping(yaml: &str) -> Mapping {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn later_layers_override_earlier_ones() {
+        let mut layers = Layers::default();
+        layers.merge(mapping("timeout: 7\nsvdir: /etc/sv"), Layer::Default);
+        layers.merge(mapping("timeout: 3"), Layer::File);
+        layers.set("svdir", "/run/sv", Layer::Cli);
+
+        assert_eq!(layers.origins["timeout"], Layer::File);
+        assert_eq!(layers.origins["svdir"], Layer::Cli);
+
+        let value = layers.into_value();
+        assert_eq!(value["timeout"], Value::from(3));
+        assert_eq!(value["svdir"], Value::from("/run/sv"));
+    }
+
+    #[test]
+    fn keeps_keys_of_earlier_layers() {
+        let mut layers = Layers::default();
+        layers.merge(mapping("timeout: 7\ncolor: true"), Layer::System);
+        layers.merge(mapping("timeout: 3"), Layer::User);
+
+        assert_eq!(layers.origins["color"], Layer::System);
+        assert_eq!(layers.into_value()["color"], Value::from(true));
+    }
+
+    #[test]
+    fn ignores_missing_files() {
+        let mut layers = Layers::default();
+        let file = env::temp_dir().join(format!("rsv-missing-{}.conf", process::id()));
+
+        assert!(layers.merge_file(&file, Layer::File).is_ok());
+        assert!(layers.origins.is_empty());
+    }
+
+    #[test]
+    fn merges_files() {
+        let mut layers = Layers::default();
+        let file = env::temp_dir().join(format!("rsv-layer-{}.conf", process::id()));
+
+        fs::write(&file, "timeout: 3\n").unwrap();
+        let result = layers.merge_file(&file, Layer::User);
+        fs::write(&file, "- timeout\n").unwrap();
+        let invalid = layers.merge_file(&file, Layer::File);
+        fs::remove_file(&file).unwrap();
+
+        assert!(result.is_ok());
+        assert!(invalid.is_err());
+        assert_eq!(layers.origins["timeout"], Layer::User);
+    }
+}
@@ -1,163 +1,606 @@
+pub mod distro;
+pub mod layer;
+pub mod svdir;
+
 use serde_derive::{Deserialize, Serialize};
+use serde_yaml::Value;
 
+use distro::DISTRO_CONF_FILE;
+use layer::{Layer, Layers};
+use std::collections::BTreeMap;
 use std::env;
 use std::error;
-use std::fs::{self, create_dir_all, File};
-use std::io::{self, stdin, Error, Write};
-use std::path::{self, Path};
-use std::process;
-use sysinfo::{ProcessExt, SystemExt};
+use std::fs::{self, create_dir_all, OpenOptions};
+use std::io::{stdin, Write};
+use std::os::unix::fs::{MetadataExt, OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use svdir::SvDir;
+
+use crate::api::ApiAllow;
+use crate::monitor::Hook;
+use crate::output::{ColorMode, OutputFormat};
+use crate::privilege::{self, BackendKind};
+use crate::sv::health::HealthCheck;
 
 pub const DEFAULT_CONF_PATH: &str = "/etc/runitsv/";
 pub const DEFAULT_CONF_FILE: &str = "default.conf";
 
+/// Environment variables overriding the config
+pub const ENV_SERVICE_PATH: &str = "RSV_SERVICE_PATH";
+pub const ENV_RUNSV_DIR: &str = "RSV_RUNSV_DIR";
+
+/// Well known service source dirs
+pub const SERVICE_PATHS: [&str; 2] = ["/etc/sv/", "/etc/runit/sv/"];
+/// Well known runsvdirs
+pub const RUNSV_DIRS: [&str; 4] = [
+    "/etc/service/",
+    "/service/",
+    "/var/service/",
+    "/run/runit/service/",
+];
+
+/// Config dir of the user, relative to $XDG_CONFIG_HOME
+pub const USER_CONF_PATH: &str = "rsv/";
+/// Config file of user services, inside of the user config dir
+pub const USER_SERVICES_CONF_FILE: &str = "user.conf";
+/// Service source dir of user services, relative to $HOME
+pub const USER_SERVICE_PATH: &str = ".local/share/sv/";
+
+/// Audit log of changes made by rsv
+pub const AUDIT_LOG: &str = "/var/log/rsv/audit.log";
+/// Audit log of user services, relative to $HOME
+pub const USER_AUDIT_LOG: &str = ".local/state/rsv/audit.log";
+
+/// Socket of the API served by `rsv daemon`
+pub const API_SOCKET: &str = "/run/rsv.sock";
+
+/// Default timeout of commands in seconds
+pub const DEFAULT_TIMEOUT: u64 = 7;
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Config {
     pub runsv_dir: String,
     pub service_path: String,
+
+    /// Seconds to wait for commands to take effect
+    pub timeout: u64,
+    /// Timeouts of specific services, overriding `timeout`
+    pub service_timeouts: BTreeMap<String, u64>,
+    /// Kill services which don't stop within the timeout
+    pub kill_on_timeout: bool,
+    pub output: OutputFormat,
+    pub color: ColorMode,
+    /// Program used to gain root privileges
+    pub privilege_backend: BackendKind,
+    /// Groups allowed to control services, applied by `rsv grant`
+    pub grants: BTreeMap<String, String>,
+    /// File changes to services get logged to. Empty to disable
+    pub audit_log: String,
+    /// Checks a started service has to pass, besides its check script
+    pub health_checks: BTreeMap<String, Vec<HealthCheck>>,
+    /// Commands `rsv monitor` runs on state changes
+    pub hooks: Vec<Hook>,
+    /// Unix socket `rsv daemon` listens on
+    pub api_socket: String,
+    /// Callers allowed to use the API of `rsv daemon`
+    pub api_allow: ApiAllow,
+    /// Token required by the REST API of `rsv serve`. Without one, services can only be read
+    pub api_token: String,
+
+    /// Whether this config manages the services
+    /// of the invoking user instead of the system
+    #[serde(skip)]
+    pub user: bool,
+
+    /// Only print what would be changed
+    #[serde(skip)]
+    pub dry_run: bool,
+
+    /// Uid and gid of the API client changes are made for.
+    /// Logged to the audit log instead of the invoking user
+    #[serde(skip)]
+    pub caller: Option<(u32, u32)>,
+
+    /// Layer each value was taken from
+    #[serde(skip)]
+    pub origins: BTreeMap<String, Layer>,
+}
+
+/// Options to load the config with
+#[derive(Debug, Default)]
+pub struct Options<'a> {
+    /// Manage the services of the invoking user
+    pub user: bool,
+    /// Additional config file
+    pub file: Option<&'a Path>,
+    /// Path or name of the runsvdir to use
+    pub svdir: Option<&'a str>,
+    /// Values set by command line flags as (key, YAML value)
+    pub overrides: Vec<(&'a str, &'a str)>,
+    /// Only print what would be changed
+    pub dry_run: bool,
 }
 
 impl Default for Config {
     fn default() -> Self {
-        let mut service_path = String::new();
+        let distro = distro::detect(&Path::new(DEFAULT_CONF_PATH).join(DISTRO_CONF_FILE));
 
-        if sys_info::linux_os_release()
-            .and_then(|f| {
-                let release;
-
-                if f.id.is_some() {
-                    release = f.id.unwrap();
-                } else if f.name.is_some() {
-                    release = f.name.unwrap()
-                } else {
-                    println!("Release is empty");
-                    return Err(sys_info::Error::IO(Error::new(io::ErrorKind::NotFound, "")));
-                }
+        let runsv_dir = distro
+            .as_ref()
+            .map(|distro| distro.runsv_dir.clone())
+            .filter(|dir| Path::new(dir).is_dir())
+            .unwrap_or_default();
 
-                match release.to_lowercase().as_str() {
-                    "artix" => service_path = String::from("/etc/runit/sv/"),
-                    "void" => service_path = String::from("/etc/sv/"),
-                    // TODO add other runit based distros
-                    _ => {
-                        return Err(sys_info::Error::IO(Error::new(io::ErrorKind::NotFound, "")));
-                    }
-                };
+        let service_path = distro
+            .map(|distro| distro.service_path)
+            .or_else(|| probe_dirs(&SERVICE_PATHS))
+            .unwrap_or_default();
 
-                Ok(())
-            })
-            .is_err()
-            || service_path.is_empty()
-        {
-            println!("Coludn't find your service source path!");
-            println!("Enter your service source dir manually (keep empty to skip):");
+        Config::with_paths(runsv_dir, service_path)
+    }
+}
 
-            // Check if path exists
-            loop {
-                stdin()
-                    .read_line(&mut service_path)
-                    .expect("Error reading from stdin");
+impl Config {
+    /// Create a new config by merging, in increasing precedence,
+    /// the built-in defaults, the system config, the user config,
+    /// the config file from `options`, the environment and `options`
+    pub fn new(options: &Options) -> Result<Self, Box<dyn error::Error>> {
+        let mut settings = Config::load(options)?;
 
-                if service_path.is_empty() {
-                    break;
-                }
+        if let Some(svdir) = options.svdir {
+            settings.select_svdir(svdir)?;
+        }
+
+        if settings.service_path.is_empty() {
+            settings.service_path = prompt_service_path()?;
+            settings
+                .origins
+                .insert("service_path".to_owned(), Layer::Prompt);
+        }
+
+        settings.init_svdir()?;
 
-                service_path = service_path.trim().to_owned();
-                if !Path::new(&service_path).exists() {
-                    println!("Path does not exists!");
-                    service_path = String::new();
-                    continue;
+        Ok(settings)
+    }
+
+    /// Merge the config layers without detecting or asking for missing values
+    pub fn load(options: &Options) -> Result<Self, Box<dyn error::Error>> {
+        let mut layers = Layers::default();
+
+        layers.merge(
+            to_mapping(&Config::defaults(options.user)?)?,
+            Layer::Default,
+        );
+
+        if options.user {
+            layers.merge_file(&Config::file_path(true)?, Layer::User)?;
+        } else {
+            layers.merge_file(&Config::file_path(false)?, Layer::System)?;
+
+            // The user config is optional for system services. When
+            // escalated, it's the config of the invoking user though
+            if let Ok(dir) = user_conf_dir() {
+                let file = dir.join(DEFAULT_CONF_FILE);
+                if is_trusted(&file) {
+                    layers.merge_file(&file, Layer::User)?;
                 }
+            }
+        }
 
-                break;
+        if let Some(file) = options.file {
+            if !file.is_file() {
+                return Err(format!("Config file {} not found", file.display()).into());
             }
+
+            if !is_trusted(file) {
+                return Err(format!("Config file {} is not owned by root", file.display()).into());
+            }
+
+            layers.merge_file(file, Layer::File)?;
+        }
+
+        // RSV_RUNSV_DIR takes precedence over SVDIR
+        for (key, var) in [
+            ("runsv_dir", "SVDIR"),
+            ("runsv_dir", ENV_RUNSV_DIR),
+            ("service_path", ENV_SERVICE_PATH),
+        ] {
+            if let Some(value) = env_var(var) {
+                layers.set(key, value, Layer::Env);
+            }
+        }
+
+        // Timeout in seconds like in runit's sv
+        if let Some(svwait) = env_var("SVWAIT") {
+            let secs = svwait
+                .parse::<u64>()
+                .map_err(|_| format!("Invalid SVWAIT '{}'", svwait))?;
+            layers.set("timeout", secs, Layer::Env);
+        }
+
+        for (key, value) in options.overrides.iter() {
+            layers.set(key, serde_yaml::from_str::<Value>(value)?, Layer::Cli);
+        }
+
+        let mut settings = Config::from_layers(layers, options.user)?;
+        settings.dry_run = options.dry_run;
+
+        Ok(settings)
+    }
+
+    /// Load the defaults and the values of the file written by `save`
+    pub fn load_file(user: bool) -> Result<Self, Box<dyn error::Error>> {
+        let mut layers = Layers::default();
+        layers.merge(to_mapping(&Config::defaults(user)?)?, Layer::Default);
+        layers.merge_file(
+            &Config::file_path(user)?,
+            if user { Layer::User } else { Layer::System },
+        )?;
+
+        Config::from_layers(layers, user)
+    }
+
+    fn from_layers(layers: Layers, user: bool) -> Result<Self, Box<dyn error::Error>> {
+        let origins = layers.origins.clone();
+        let mut settings: Config = serde_yaml::from_value(layers.into_value())?;
+        settings.user = user;
+        settings.origins = origins;
+        Ok(settings)
+    }
+
+    /// Default config for system or user services
+    pub fn defaults(user: bool) -> Result<Self, Box<dyn error::Error>> {
+        if user {
+            Config::default_user()
+        } else {
+            Ok(Config::default())
         }
+    }
 
+    /// Default config using the given paths
+    fn with_paths(runsv_dir: String, service_path: String) -> Self {
         Config {
-            runsv_dir: String::new(),
+            runsv_dir,
             service_path,
+            timeout: DEFAULT_TIMEOUT,
+            service_timeouts: BTreeMap::new(),
+            kill_on_timeout: true,
+            output: OutputFormat::default(),
+            color: ColorMode::default(),
+            privilege_backend: BackendKind::default(),
+            grants: BTreeMap::new(),
+            audit_log: AUDIT_LOG.to_owned(),
+            health_checks: BTreeMap::new(),
+            hooks: Vec::new(),
+            api_socket: API_SOCKET.to_owned(),
+            api_allow: ApiAllow::default(),
+            api_token: String::new(),
+            user: false,
+            dry_run: false,
+            caller: None,
+            origins: BTreeMap::new(),
         }
     }
-}
 
-impl Config {
-    /// Create a new config
-    pub fn new() -> Result<Self, Box<dyn error::Error>> {
-        let conf_path = path::Path::new(DEFAULT_CONF_PATH);
-        if !conf_path.exists() {
-            #[cfg(feature = "auto_sudo")]
-            sudo::escalate_if_needed()?;
-
-            create_dir_all(conf_path)?;
-        }
-
-        let mut settings: Config;
-        let mut need_save = false;
-        let file = conf_path.join(DEFAULT_CONF_FILE);
-        if file.exists() {
-            // Read existing config
-            settings = serde_yaml::from_str(fs::read_to_string(&file)?.as_str())?;
+    /// Default config for user services
+    fn default_user() -> Result<Self, Box<dyn error::Error>> {
+        let service_path = home_dir()?.join(USER_SERVICE_PATH);
+
+        let mut config =
+            Config::with_paths(String::new(), service_path.to_string_lossy().into_owned());
+        config.user = true;
+        config.audit_log = home_dir()?
+            .join(USER_AUDIT_LOG)
+            .to_string_lossy()
+            .into_owned();
+
+        Ok(config)
+    }
+
+    /// Timeout of commands sent to `service`. A timeout set by
+    /// a command line flag overrides the ones of single services
+    pub fn timeout_for(&self, service: &str) -> Duration {
+        let secs = match self.service_timeouts.get(service) {
+            Some(secs) if self.origins.get("timeout") != Some(&Layer::Cli) => *secs,
+            _ => self.timeout,
+        };
+
+        Duration::from_secs(secs)
+    }
+
+    /// The config file written by `save`
+    pub fn file_path(user: bool) -> Result<PathBuf, Box<dyn error::Error>> {
+        if user {
+            Ok(user_conf_dir()?.join(USER_SERVICES_CONF_FILE))
         } else {
-            // Create new config from default value
-            settings = Config::default();
-            need_save = true;
+            Ok(Path::new(DEFAULT_CONF_PATH).join(DEFAULT_CONF_FILE))
         }
+    }
+
+    /// Describe each value and the layer it was taken from
+    pub fn describe(&self) -> Result<String, Box<dyn error::Error>> {
+        Ok(to_mapping(self)?
+            .into_iter()
+            .filter_map(|(key, value)| {
+                let key = key.as_str()?.to_owned();
+                let layer = self.origins.get(&key).unwrap_or(&Layer::Default);
+
+                Some(format!(
+                    "{}={} ({})\n",
+                    key,
+                    layer::value_string(&value),
+                    layer.value()
+                ))
+            })
+            .collect())
+    }
+
+    /// Get a value by its key. Keys of nested values are separated by dots
+    pub fn get(&self, key: &str) -> Result<String, Box<dyn error::Error>> {
+        let root = serde_yaml::to_value(self)?;
+
+        key.split('.')
+            .try_fold(&root, |value, part| value.get(part))
+            .map(layer::value_string)
+            .ok_or_else(|| format!("Unknown config key '{}'", key).into())
+    }
 
-        if init_svdir(&mut settings) {
-            need_save = true
+    /// Set a value by its key. `value` is parsed as YAML, falling
+    /// back to a string if that doesn't fit the type of the key
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), Box<dyn error::Error>> {
+        let root = serde_yaml::to_value(&*self)?;
+        let unknown_key = || format!("Unknown config key '{}'", key);
+
+        let mut parts: Vec<&str> = key.split('.').collect();
+        let last = parts.pop().ok_or_else(unknown_key)?;
+
+        // Only values of existing keys or entries of nested mappings can be set
+        if parts.is_empty() && root.get(last).is_none() {
+            return Err(unknown_key().into());
         }
 
-        if need_save {
-            #[cfg(feature = "auto_sudo")]
-            sudo::escalate_if_needed()?;
+        let parent = parts
+            .iter()
+            .try_fold(&root, |value, part| value.get(part))
+            .filter(|value| value.is_mapping())
+            .ok_or_else(unknown_key)?;
+
+        // The config with `value` set
+        let with_value = |value: Value| {
+            let mut mapping = parent.as_mapping().cloned().unwrap_or_default();
+            mapping.insert(last.into(), value);
+
+            let mut root = root.clone();
+            let target = parts
+                .iter()
+                .try_fold(&mut root, |value, part| value.get_mut(part));
+            if let Some(target) = target {
+                *target = Value::Mapping(mapping);
+            }
+
+            serde_yaml::from_value::<Config>(root)
+        };
+
+        // Eg. "123" or "true" for a path
+        let parsed = serde_yaml::from_str(value).unwrap_or_else(|_| Value::from(value));
+        let mut settings = with_value(parsed)
+            .or_else(|err| with_value(Value::from(value)).map_err(|_| err))
+            .map_err(|err| format!("Invalid value for '{}': {}", key, err))?;
+        settings.user = self.user;
+        settings.origins = self.origins.clone();
 
-            settings.save()?;
+        *self = settings;
+        Ok(())
+    }
+
+    /// Check the config for problems
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        for (key, dir) in [
+            ("service_path", &self.service_path),
+            ("runsv_dir", &self.runsv_dir),
+        ] {
+            if dir.is_empty() {
+                problems.push(format!("{} is not set", key));
+            } else if !Path::new(dir).is_dir() {
+                problems.push(format!("{}: {} is not a directory", key, dir));
+            }
         }
 
-        Ok(settings)
+        if !self.runsv_dir.is_empty()
+            && !self
+                .svdirs()
+                .iter()
+                .any(|svdir| svdir.matches(&self.runsv_dir))
+        {
+            problems.push(format!(
+                "runsv_dir: {} is not served by a running runsvdir",
+                self.runsv_dir
+            ));
+        }
+
+        problems
     }
 
-    /// Save the config
-    pub fn save(&self) -> Result<(), Box<dyn error::Error>> {
-        File::create(path::Path::new(DEFAULT_CONF_PATH).join(DEFAULT_CONF_FILE))?
-            .write_all(serde_yaml::to_string(self)?.as_bytes())?;
+    /// All running runsvdir instances this config can manage
+    pub fn svdirs(&self) -> Vec<SvDir> {
+        if self.user {
+            // User services are supervised by a runsvdir owned by the user
+            svdir::find_by_uid(unsafe { libc::getuid() })
+        } else {
+            svdir::find_all()
+        }
+    }
+
+    /// Use the runsvdir matching `query` (its path or name) for this run
+    pub fn select_svdir(&mut self, query: &str) -> Result<(), Box<dyn error::Error>> {
+        self.runsv_dir = match self.svdirs().into_iter().find(|svdir| svdir.matches(query)) {
+            Some(svdir) => svdir.dir,
+            // Allow svdirs which aren't supervised right now
+            None if Path::new(query).is_dir() => query.to_owned(),
+            None => return Err(format!("No runsvdir found for '{}'", query).into()),
+        };
 
+        self.origins.insert("runsv_dir".to_owned(), Layer::Cli);
         Ok(())
     }
-}
 
-fn init_svdir(config: &mut Config) -> bool {
-    // Check environment variable first
-    if let Ok(var) = env::var("SVDIR") {
-        if var.is_empty() {
-            config.runsv_dir = var;
-            return false;
+    /// Backend used to gain root privileges. User services never need root
+    pub fn escalation(&self) -> BackendKind {
+        if self.user {
+            BackendKind::None
+        } else {
+            self.privilege_backend
         }
     }
 
-    // Only use config if usable
-    if config.runsv_dir.len() > 1 && Path::new(&config.runsv_dir.as_str()).exists() {
-        return false;
+    /// Save the config. Root privileges are only requested if required
+    pub fn save(&self) -> Result<String, Box<dyn error::Error>> {
+        let file = Config::file_path(self.user)?;
+
+        if self.dry_run {
+            return Ok(format!("Would write config to {}\n", file.display()));
+        }
+
+        privilege::retry_as_root(self.write(), self.escalation())?;
+        Ok(format!("Config saved to {}\n", file.display()))
     }
 
-    let mut sys = sysinfo::System::new();
-    sys.refresh_processes();
+    fn write(&self) -> Result<(), Box<dyn error::Error>> {
+        let file = Config::file_path(self.user)?;
 
-    if let Some(proc) = sys.process_by_name("runsvdir").into_iter().next() {
-        let mut cmd = proc.cmd().iter(); // runsvdir -P <dir> [log]
-        if cmd.next().is_some() {
-            if let Some(opt) = cmd.next() {
-                if opt == "-P" {
-                    if let Some(dir) = cmd.next() {
-                        if Path::new(dir).is_dir() {
-                            config.runsv_dir = dir.clone();
-                            return true;
-                        }
-                    }
-                }
+        if let Some(dir) = file.parent() {
+            create_dir_all(dir)?;
+        }
+
+        // Write a temporary file first to never leave a broken config behind
+        let mut tmp_name = file.file_name().unwrap_or_default().to_owned();
+        tmp_name.push(".tmp");
+        let tmp = file.with_file_name(tmp_name);
+
+        // The token of the REST API must not be readable by other users
+        let mode = if self.api_token.is_empty() {
+            0o644
+        } else {
+            0o600
+        };
+
+        let mut tmp_file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(mode)
+            .open(&tmp)?;
+
+        // The mode only applies to new files
+        tmp_file.set_permissions(fs::Permissions::from_mode(mode))?;
+        tmp_file.write_all(serde_yaml::to_string(self)?.as_bytes())?;
+        fs::rename(&tmp, &file)?;
+
+        Ok(())
+    }
+
+    /// Find the runsvdir to use if the configured one is not usable
+    pub fn init_svdir(&mut self) -> Result<(), Box<dyn error::Error>> {
+        // Only use config if usable
+        if self.runsv_dir.len() > 1 && Path::new(&self.runsv_dir.as_str()).exists() {
+            return Ok(());
+        }
+
+        self.origins.insert("runsv_dir".to_owned(), Layer::Detected);
+
+        // Prefer the runsvdir of root for system services
+        let svdirs = self.svdirs();
+        if let Some(svdir) = svdirs
+            .iter()
+            .find(|svdir| svdir.uid == 0)
+            .or_else(|| svdirs.first())
+        {
+            self.runsv_dir = svdir.dir.clone();
+            return Ok(());
+        }
+
+        // runsvdir might not be running yet, eg. in container builds
+        if !self.user {
+            if let Some(dir) = probe_dirs(&RUNSV_DIRS) {
+                self.runsv_dir = dir;
+                return Ok(());
             }
         }
+
+        Err("Can't find runsvdir! make sure you have a running 'runsvdir' process!".into())
+    }
+}
+
+fn to_mapping(config: &Config) -> Result<serde_yaml::Mapping, Box<dyn error::Error>> {
+    match serde_yaml::to_value(config)? {
+        serde_yaml::Value::Mapping(mapping) => Ok(mapping),
+        _ => Err("Config is not a mapping".into()),
+    }
+}
+
+/// Config dir of the invoking user
+fn user_conf_dir() -> Result<PathBuf, Box<dyn error::Error>> {
+    let config_home = match env_var("XDG_CONFIG_HOME") {
+        Some(dir) => PathBuf::from(dir),
+        None => home_dir()?.join(".config"),
+    };
+
+    Ok(config_home.join(USER_CONF_PATH))
+}
+
+fn home_dir() -> Result<PathBuf, Box<dyn error::Error>> {
+    match env::var("HOME") {
+        Ok(home) if !home.is_empty() => Ok(PathBuf::from(home)),
+        _ => Err("$HOME is not set".into()),
+    }
+}
+
+/// Ask the user for the service source path.
+/// Fails if stdin is not a terminal
+fn prompt_service_path() -> Result<String, Box<dyn error::Error>> {
+    if unsafe { libc::isatty(libc::STDIN_FILENO) } != 1 {
+        return Err(format!(
+            "Couldn't find your service source path! Set {} or use --config",
+            ENV_SERVICE_PATH
+        )
+        .into());
+    }
+
+    println!("Couldn't find your service source path!");
+    println!("Enter your service source dir manually (keep empty to skip):");
+
+    // Check if path exists
+    loop {
+        let mut service_path = String::new();
+        stdin().read_line(&mut service_path)?;
+
+        let service_path = service_path.trim();
+        if service_path.is_empty() || Path::new(service_path).exists() {
+            return Ok(service_path.to_owned());
+        }
+
+        println!("Path does not exists!");
     }
+}
+
+/// Return the first existing directory of `dirs`
+fn probe_dirs(dirs: &[&str]) -> Option<String> {
+    dirs.iter()
+        .find(|dir| Path::new(dir).is_dir())
+        .map(|dir| dir.to_string())
+}
+
+/// Whether `file` may be merged into the config. Root only reads files it owns,
+/// so configs of the invoking user can't change what escalated runs do
+fn is_trusted(file: &Path) -> bool {
+    let euid = unsafe { libc::geteuid() };
+    euid != 0 || fs::symlink_metadata(file).map_or(true, |md| md.uid() == 0)
+}
 
-    println!("Can't find runsvdir! make sure you have a running 'runsvdir' process!");
-    process::exit(1);
+/// Read a non empty environment variable
+fn env_var(key: &str) -> Option<String> {
+    env::var(key).ok().filter(|var| !var.is_empty())
 }
@@ -0,0 +1,91 @@
+use std::fs;
+use std::path::Path;
+use sysinfo::{ProcessExt, SystemExt};
+
+use crate::users::user_name;
+
+/// A running runsvdir instance
+#[derive(Debug, Clone, PartialEq)]
+pub struct SvDir {
+    pub dir: String,
+    pub pid: i32,
+    pub uid: u32,
+}
+
+impl SvDir {
+    /// Short name of the svdir, used to pick it by name
+    pub fn name(&self) -> String {
+        Path::new(&self.dir)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| self.dir.clone())
+    }
+
+    /// Name of the user owning the runsvdir process
+    pub fn owner(&self) -> String {
+        user_name(self.uid).unwrap_or_else(|| self.uid.to_string())
+    }
+
+    /// Check whether `query` is the path or the name of the svdir
+    pub fn matches(&self, query: &str) -> bool {
+        if self.dir == query || self.name() == query {
+            return true;
+        }
+
+        match (fs::canonicalize(&self.dir), fs::canonicalize(query)) {
+            (Ok(dir), Ok(query)) => dir == query,
+            _ => false,
+        }
+    }
+
+    /// Format the svdir as line for the `svdirs` command
+    pub fn format(&self) -> String {
+        format!(
+            "{}: {} (pid {}, owner {})\n",
+            self.name(),
+            self.dir,
+            self.pid,
+            self.owner()
+        )
+    }
+}
+
+/// Find all running runsvdir instances
+pub fn find_all() -> Vec<SvDir> {
+    let mut sys = sysinfo::System::new();
+    sys.refresh_processes();
+
+    let mut svdirs: Vec<SvDir> = sys
+        .process_by_name("runsvdir")
+        .into_iter()
+        .filter_map(|proc| {
+            // runsvdir [-P] <dir> [log]
+            let dir = proc
+                .cmd()
+                .iter()
+                .skip(1)
+                .find(|arg| !arg.starts_with('-'))?;
+
+            if !Path::new(dir).is_dir() {
+                return None;
+            }
+
+            Some(SvDir {
+                dir: dir.clone(),
+                pid: proc.pid(),
+                uid: proc.uid,
+            })
+        })
+        .collect();
+
+    svdirs.sort_by_key(|svdir| svdir.pid);
+    svdirs
+}
+
+/// Find all running runsvdir instances owned by `uid`
+pub fn find_by_uid(uid: u32) -> Vec<SvDir> {
+    find_all()
+        .into_iter()
+        .filter(|svdir| svdir.uid == uid)
+        .collect()
+}
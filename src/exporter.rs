@@ -0,0 +1,239 @@
+use std::collections::BTreeMap;
+use std::error;
+use std::fmt::Write as _;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::config::Config;
+use crate::sv::info::ServiceInfo;
+use crate::sv::status::{DOWN, FINISH, RUN};
+use crate::watch::{Watcher, POLL_INTERVAL};
+
+/// Default address of the metrics endpoint
+pub const DEFAULT_LISTEN: &str = "127.0.0.1:9861";
+
+/// Time a client gets to send its request line
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Service states and the restarts observed since startup
+#[derive(Default)]
+struct Metrics {
+    services: Vec<ServiceInfo>,
+    restarts: BTreeMap<String, u64>,
+}
+
+/// Serve the metrics of all services on /metrics of `addr` until killed
+pub fn serve(config: Config, addr: &str) -> Result<String, Box<dyn error::Error>> {
+    let listener = TcpListener::bind(addr)?;
+    let metrics = Arc::new(Mutex::new(Metrics::default()));
+
+    let mut watcher = Watcher::new(config);
+    watcher.poll()?;
+    metrics.lock().unwrap().services = watcher.services().cloned().collect();
+
+    let polled = Arc::clone(&metrics);
+    thread::spawn(move || loop {
+        thread::sleep(POLL_INTERVAL);
+
+        let transitions = match watcher.poll() {
+            Ok(transitions) => transitions,
+            Err(err) => {
+                eprintln!("warning: {}", err);
+                continue;
+            }
+        };
+
+        let mut metrics = polled.lock().unwrap();
+        for transition in transitions.iter().filter(|t| t.event.is_start()) {
+            *metrics
+                .restarts
+                .entry(transition.new.name.clone())
+                .or_default() += 1;
+        }
+        metrics.services = watcher.services().cloned().collect();
+    });
+
+    eprintln!("Serving metrics on http://{}/metrics", addr);
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(_) => continue,
+        };
+
+        // A client that never sends its request must not block the others
+        let metrics = Arc::clone(&metrics);
+        thread::spawn(move || {
+            if let Err(err) = respond(stream, &metrics) {
+                eprintln!("warning: {}", err);
+            }
+        });
+    }
+
+    Ok(String::new())
+}
+
+fn respond(mut stream: TcpStream, metrics: &Mutex<Metrics>) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+
+    let path = request_line.split_whitespace().nth(1).unwrap_or_default();
+    let (status, body) = if path == "/metrics" {
+        ("200 OK", render(&metrics.lock().unwrap()))
+    } else {
+        ("404 Not Found", "Not found, see /metrics\n".to_owned())
+    };
+
+    write!(
+        stream,
+        "HTTP/1.0 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
+
+/// Metrics in the prometheus text format
+fn render(metrics: &Metrics) -> String {
+    let mut s = String::new();
+    let services = &metrics.services;
+
+    gauge(
+        &mut s,
+        "up",
+        "Whether the service is running",
+        services,
+        |info| (info.state == RUN) as i64,
+    );
+    gauge(
+        &mut s,
+        "enabled",
+        "Whether the service is enabled",
+        services,
+        |info| info.enabled as i64,
+    );
+    gauge(
+        &mut s,
+        "pid",
+        "Pid of the service, 0 if not running",
+        services,
+        |info| info.pid as i64,
+    );
+    gauge(
+        &mut s,
+        "seconds_since_change",
+        "Seconds since the last state change",
+        services,
+        |info| info.uptime as i64,
+    );
+    gauge(
+        &mut s,
+        "normally_up",
+        "Whether runsv starts the service by itself",
+        services,
+        |info| info.normally_up as i64,
+    );
+    gauge(
+        &mut s,
+        "paused",
+        "Whether the service is paused",
+        services,
+        |info| info.paused as i64,
+    );
+
+    one_hot(
+        &mut s,
+        "state",
+        "Current state of the service",
+        services,
+        &[RUN, DOWN, FINISH],
+        |info| &info.state,
+    );
+    one_hot(
+        &mut s,
+        "want",
+        "State runsv was asked to bring the service into",
+        services,
+        &["up", "down", "none"],
+        |info| &info.want,
+    );
+
+    header(
+        &mut s,
+        "restarts_total",
+        "Starts observed while the exporter runs",
+        "counter",
+    );
+    for info in services {
+        let restarts = metrics.restarts.get(&info.name).copied().unwrap_or(0);
+        let _ = writeln!(
+            s,
+            "rsv_service_restarts_total{{service=\"{}\"}} {}",
+            escape(&info.name),
+            restarts
+        );
+    }
+
+    s
+}
+
+fn gauge<F>(s: &mut String, name: &str, help: &str, services: &[ServiceInfo], value: F)
+where
+    F: Fn(&ServiceInfo) -> i64,
+{
+    header(s, name, help, "gauge");
+    for info in services {
+        let _ = writeln!(
+            s,
+            "rsv_service_{}{{service=\"{}\"}} {}",
+            name,
+            escape(&info.name),
+            value(info)
+        );
+    }
+}
+
+/// A gauge per possible value, set to 1 for the current one
+fn one_hot<F>(
+    s: &mut String,
+    name: &str,
+    help: &str,
+    services: &[ServiceInfo],
+    values: &[&str],
+    current: F,
+) where
+    F: Fn(&ServiceInfo) -> &String,
+{
+    header(s, name, help, "gauge");
+    for info in services {
+        for value in values {
+            let _ = writeln!(
+                s,
+                "rsv_service_{}{{service=\"{}\",{}=\"{}\"}} {}",
+                name,
+                escape(&info.name),
+                name,
+                value,
+                (current(info) == value) as i64
+            );
+        }
+    }
+}
+
+fn header(s: &mut String, name: &str, help: &str, kind: &str) {
+    let _ = writeln!(s, "# HELP rsv_service_{} {}", name, help);
+    let _ = writeln!(s, "# TYPE rsv_service_{} {}", name, kind);
+}
+
+/// Escape a label value
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
@@ -0,0 +1,300 @@
+use clap::ArgMatches;
+use regex::Regex;
+
+use std::cmp::Ordering;
+use std::error;
+use std::time::Duration;
+
+use crate::sv::info::ServiceInfo;
+use crate::sv::status::{DOWN, RUN};
+
+/// Keys services can be sorted by
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortBy {
+    Name,
+    State,
+    Uptime,
+    Pid,
+}
+
+impl SortBy {
+    pub fn parse(s: &str) -> Result<SortBy, Box<dyn error::Error>> {
+        Ok(match s {
+            "name" => SortBy::Name,
+            "state" => SortBy::State,
+            "uptime" => SortBy::Uptime,
+            "pid" => SortBy::Pid,
+            _ => return Err(format!("Unknown sort key '{}'", s).into()),
+        })
+    }
+
+    pub fn next(self) -> SortBy {
+        match self {
+            SortBy::Name => SortBy::State,
+            SortBy::State => SortBy::Uptime,
+            SortBy::Uptime => SortBy::Pid,
+            SortBy::Pid => SortBy::Name,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SortBy::Name => "name",
+            SortBy::State => "state",
+            SortBy::Uptime => "uptime",
+            SortBy::Pid => "pid",
+        }
+    }
+
+    /// Order of `a` and `b`, falling back to their names
+    pub fn compare(self, a: &ServiceInfo, b: &ServiceInfo) -> Ordering {
+        let ordering = match self {
+            SortBy::Name => Ordering::Equal,
+            SortBy::State => a.state.cmp(&b.state),
+            SortBy::Uptime => a.uptime.cmp(&b.uptime),
+            SortBy::Pid => a.pid.cmp(&b.pid),
+        };
+
+        ordering.then_with(|| a.name.cmp(&b.name))
+    }
+}
+
+/// Filters and order of the list subcommand
+#[derive(Debug, Clone)]
+pub struct ListFilter {
+    /// Include disabled services
+    pub all: bool,
+    pub enabled_only: bool,
+    pub disabled_only: bool,
+    pub name: Option<Regex>,
+    /// One of run, down or finish
+    pub state: Option<String>,
+    pub paused: bool,
+    /// One of up or down
+    pub want: Option<String>,
+    pub normally_down: bool,
+    pub min_uptime: Option<Duration>,
+    pub max_uptime: Option<Duration>,
+    pub sort: SortBy,
+    pub reverse: bool,
+}
+
+impl ListFilter {
+    pub fn from_matches(matches: &ArgMatches) -> Result<ListFilter, Box<dyn error::Error>> {
+        // --up and --down are shortcuts of --state
+        let state = match matches.value_of("state") {
+            Some(state) => Some(state),
+            None if matches.is_present("up") => Some(RUN),
+            None if matches.is_present("down") => Some(DOWN),
+            None => None,
+        };
+
+        Ok(ListFilter {
+            all: matches.is_present("all"),
+            enabled_only: matches.is_present("enabled"),
+            disabled_only: matches.is_present("disabled"),
+            name: matches
+                .value_of("name")
+                .map(|name| Regex::new(name).map_err(|err| format!("Invalid --name: {}", err)))
+                .transpose()?,
+            state: state.map(|state| state.to_owned()),
+            paused: matches.is_present("paused"),
+            want: matches.value_of("want").map(|want| want.to_owned()),
+            normally_down: matches.is_present("normally-down"),
+            min_uptime: parse_uptime(matches.value_of("min-uptime"), "--min-uptime")?,
+            max_uptime: parse_uptime(matches.value_of("max-uptime"), "--max-uptime")?,
+            sort: SortBy::parse(matches.value_of("sort").unwrap_or("name"))?,
+            reverse: matches.is_present("reverse"),
+        })
+    }
+
+    /// Whether the service described by `info` should be listed
+    pub fn matches(&self, info: &ServiceInfo) -> bool {
+        // Disabled services are only listed if asked for
+        if !(info.enabled || self.all || self.disabled_only) {
+            return false;
+        }
+
+        if (self.enabled_only && !info.enabled) || (self.disabled_only && info.enabled) {
+            return false;
+        }
+
+        if let Some(ref name) = self.name {
+            if !name.is_match(&info.name) {
+                return false;
+            }
+        }
+
+        if self.state.iter().any(|state| *state != info.state)
+            || self.want.iter().any(|want| *want != info.want)
+        {
+            return false;
+        }
+
+        if (self.paused && !info.paused) || (self.normally_down && info.normally_up) {
+            return false;
+        }
+
+        let uptime = Duration::from_secs(info.uptime);
+        if self.min_uptime.is_some_and(|min| uptime < min)
+            || self.max_uptime.is_some_and(|max| uptime > max)
+        {
+            return false;
+        }
+
+        true
+    }
+
+    /// Order of `a` and `b` in the list
+    pub fn compare(&self, a: &ServiceInfo, b: &ServiceInfo) -> Ordering {
+        let ordering = self.sort.compare(a, b);
+
+        if self.reverse {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+}
+
+fn parse_uptime(
+    value: Option<&str>,
+    flag: &str,
+) -> Result<Option<Duration>, Box<dyn error::Error>> {
+    value
+        .map(|value| {
+            humantime::parse_duration(value)
+                // Plain numbers are seconds, like --timeout
+                .or_else(|err| value.parse().map(Duration::from_secs).map_err(|_| err))
+                .map_err(|err| format!("Invalid {} '{}': {}", flag, value, err).into())
+        })
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(name: &str, enabled: bool, state: &str, uptime: u64) -> ServiceInfo {
+        ServiceInfo {
+            name: name.to_owned(),
+            enabled,
+            state: state.to_owned(),
+            pid: if state == RUN { 100 } else { 0 },
+            uptime,
+            normally_up: true,
+            paused: false,
+            want: "up".to_owned(),
+            term: false,
+            svdir: None,
+        }
+    }
+
+    fn filter() -> ListFilter {
+        ListFilter {
+            all: false,
+            enabled_only: false,
+            disabled_only: false,
+            name: None,
+            state: None,
+            paused: false,
+            want: None,
+            normally_down: false,
+            min_uptime: None,
+            max_uptime: None,
+            sort: SortBy::Name,
+            reverse: false,
+        }
+    }
+
+    #[test]
+    fn lists_enabled_services_by_default() {
+        let filter = filter();
+
+        assert!(filter.matches(&info("sshd", true, RUN, 10)));
+        assert!(!filter.matches(&info("cupsd", false, DOWN, 0)));
+    }
+
+    #[test]
+    fn lists_disabled_services_with_all() {
+        let filter = ListFilter {
+            all: true,
+            ..filter()
+        };
+
+        assert!(filter.matches(&info("sshd", true, RUN, 10)));
+        assert!(filter.matches(&info("cupsd", false, DOWN, 0)));
+    }
+
+    #[test]
+    fn lists_only_disabled_services() {
+        let filter = ListFilter {
+            disabled_only: true,
+            ..filter()
+        };
+
+        assert!(!filter.matches(&info("sshd", true, RUN, 10)));
+        assert!(filter.matches(&info("cupsd", false, DOWN, 0)));
+    }
+
+    #[test]
+    fn matches_name_regex() {
+        let filter = ListFilter {
+            name: Some(Regex::new("^ss").unwrap()),
+            ..filter()
+        };
+
+        assert!(filter.matches(&info("sshd", true, RUN, 10)));
+        assert!(!filter.matches(&info("openssh", true, RUN, 10)));
+    }
+
+    #[test]
+    fn matches_uptime_range() {
+        let filter = ListFilter {
+            min_uptime: Some(Duration::from_secs(60)),
+            max_uptime: Some(Duration::from_secs(3600)),
+            ..filter()
+        };
+
+        assert!(!filter.matches(&info("a", true, RUN, 59)));
+        assert!(filter.matches(&info("b", true, RUN, 60)));
+        assert!(filter.matches(&info("c", true, RUN, 3600)));
+        assert!(!filter.matches(&info("d", true, RUN, 3601)));
+    }
+
+    #[test]
+    fn sorts_by_key_then_name() {
+        let a = info("a", true, RUN, 50);
+        let b = info("b", true, RUN, 10);
+        let c = info("c", true, RUN, 10);
+
+        assert_eq!(SortBy::Name.compare(&a, &b), Ordering::Less);
+        assert_eq!(SortBy::Uptime.compare(&a, &b), Ordering::Greater);
+        assert_eq!(SortBy::Uptime.compare(&b, &c), Ordering::Less);
+    }
+
+    #[test]
+    fn reverses_order() {
+        let filter = ListFilter {
+            reverse: true,
+            ..filter()
+        };
+        let a = info("a", true, RUN, 0);
+        let b = info("b", true, RUN, 0);
+
+        assert_eq!(filter.compare(&a, &b), Ordering::Greater);
+    }
+
+    #[test]
+    fn parses_uptime_as_duration_or_seconds() {
+        assert_eq!(
+            parse_uptime(Some("1h"), "--min-uptime").unwrap(),
+            Some(Duration::from_secs(3600))
+        );
+        assert_eq!(
+            parse_uptime(Some("90"), "--min-uptime").unwrap(),
+            Some(Duration::from_secs(90))
+        );
+        assert!(parse_uptime(Some("soon"), "--min-uptime").is_err());
+    }
+}
@@ -1,7 +1,21 @@
+mod api;
 mod args;
+mod audit;
+mod completions;
 mod config;
+mod exporter;
+mod filter;
+mod man;
+mod monitor;
+mod output;
+mod privilege;
 mod run;
+mod state;
 mod sv;
+mod table;
+mod tui;
+mod users;
+mod watch;
 
 use clap::App;
 use clap_generate::{
@@ -9,38 +23,49 @@ use clap_generate::{
     generators::{Bash, Elvish, Fish, Zsh},
     Generator,
 };
+use std::path::Path;
 
 fn main() {
     let app = args::get_cli().get_matches();
 
-    // Run generator command if desired
-    if let Some(generator) = app.value_of("generator") {
-        generate_completions(generator);
-        return;
-    }
+    let result = match app.value_of("generator") {
+        Some("man") => man::generate(app.value_of("out-dir").map(Path::new)),
+        // Run generator command if desired
+        Some(generator) => {
+            generate_completions(generator);
+            return;
+        }
+        None => run::run(&app),
+    };
 
-    match run::run(&app) {
+    match result {
         Ok(s) => print!("{}", s),
-        Err(e) => eprintln!("An error occured: {}", e),
+        Err(e) => {
+            eprintln!("An error occured: {}", e);
+            std::process::exit(1);
+        }
     }
 }
 
 fn generate_completions(generator: &str) {
-    let mut app = args::get_cli();
-    match generator {
-        "bash" => print_completions(&mut app, Bash),
-        "elvish" => print_completions(&mut app, Elvish),
-        "fish" => print_completions(&mut app, Fish),
-        "zsh" => print_completions(&mut app, Zsh),
-        _ => println!("Unknown generator"),
-    }
+    let mut app = args::get_public_cli();
+    let script = match generator {
+        "bash" => completions_script(&mut app, Bash),
+        "elvish" => completions_script(&mut app, Elvish),
+        "fish" => completions_script(&mut app, Fish),
+        "zsh" => completions_script(&mut app, Zsh),
+        _ => {
+            println!("Unknown generator");
+            return;
+        }
+    };
+
+    // Service names are completed by calling `rsv __complete`
+    print!("{}", completions::add_dynamic(generator, script));
 }
 
-fn print_completions<G: Generator>(app: &mut App, generator: G) {
-    generate::<G, _>(
-        generator,
-        app,
-        app.get_name().to_string(),
-        &mut std::io::stdout(),
-    );
+fn completions_script<G: Generator>(app: &mut App, generator: G) -> String {
+    let mut script = Vec::new();
+    generate::<G, _>(generator, app, app.get_name().to_string(), &mut script);
+    String::from_utf8_lossy(&script).into_owned()
 }
@@ -0,0 +1,466 @@
+use clap::{crate_version, App, AppSettings, Arg, ArgSettings};
+
+use std::error;
+use std::fs;
+use std::path::Path;
+
+use crate::args;
+use crate::config::{
+    API_SOCKET, AUDIT_LOG, DEFAULT_CONF_FILE, DEFAULT_CONF_PATH, DEFAULT_TIMEOUT, ENV_RUNSV_DIR,
+    ENV_SERVICE_PATH, USER_AUDIT_LOG, USER_CONF_PATH, USER_SERVICES_CONF_FILE, USER_SERVICE_PATH,
+};
+use crate::sv::health::HEALTH_FILE;
+
+/// Section of all pages
+const SECTION: &str = "8";
+
+/// Print rsv(8), or write it and the pages of all subcommands into `out_dir`
+pub fn generate(out_dir: Option<&Path>) -> Result<String, Box<dyn error::Error>> {
+    let pages = pages(&args::get_cli());
+
+    let out_dir = match out_dir {
+        Some(dir) => dir,
+        None => return Ok(pages[0].1.clone()),
+    };
+
+    fs::create_dir_all(out_dir)?;
+
+    let mut s = String::new();
+    for (name, page) in pages {
+        let file = out_dir.join(format!("{}.{}", name, SECTION));
+        fs::write(&file, page).map_err(|err| format!("{}: {}", file.display(), err))?;
+        s.push_str(&format!("Wrote {}\n", file.display()));
+    }
+
+    Ok(s)
+}
+
+/// Names and contents of rsv(8) and the pages of all visible subcommands
+fn pages(app: &App) -> Vec<(String, String)> {
+    let name = app.get_name();
+    let mut pages = vec![(name.to_owned(), main_page(app))];
+
+    for sub in subcommands(app) {
+        let page_name = format!("{}-{}", name, sub.get_name());
+        pages.push((page_name.clone(), subcommand_page(app, sub, &page_name)));
+    }
+
+    pages
+}
+
+fn main_page(app: &App) -> String {
+    let name = app.get_name();
+    let mut s = header(name, app);
+
+    s.push_str(".SH NAME\n");
+    s.push_str(&format!(
+        "{} \\- {}\n",
+        name,
+        escape(app.get_about().unwrap_or_default())
+    ));
+
+    s.push_str(".SH SYNOPSIS\n");
+    s.push_str(&format!(
+        ".B {}\n[\\fIOPTIONS\\fR] \\fISUBCOMMAND\\fR [\\fIARGS\\fR]\n",
+        name
+    ));
+
+    s.push_str(".SH DESCRIPTION\n");
+    s.push_str(&format!(
+        "{} controls runit services through the supervise directories of runsv(8), \
+         like sv(8) does. Services are enabled by linking their service directory \
+         into the directory supervised by runsvdir(8).\n",
+        escape(name)
+    ));
+    s.push_str(
+        ".PP\nOperations which need root are retried using sudo, doas or run0. \
+         Read-only commands only need root if the supervise directories aren't readable.\n",
+    );
+
+    s.push_str(".SH OPTIONS\n");
+    for arg in visible_args(app).filter(|arg| !is_global(arg)) {
+        s.push_str(&describe_arg(arg));
+    }
+
+    s.push_str(".SH GLOBAL OPTIONS\n");
+    s.push_str("These options are accepted by all subcommands.\n");
+    for arg in visible_args(app).filter(|arg| is_global(arg)) {
+        s.push_str(&describe_arg(arg));
+    }
+
+    s.push_str(".SH SUBCOMMANDS\n");
+    for sub in subcommands(app) {
+        s.push_str(&format!(
+            ".TP\n\\fB{}\\-{}\\fR({})\n{}\n",
+            name,
+            escape(sub.get_name()),
+            SECTION,
+            escape(sub.get_about().unwrap_or_default())
+        ));
+    }
+
+    s.push_str(&exit_status());
+    s.push_str(&environment());
+    s.push_str(&files());
+    s.push_str(&configuration());
+
+    s.push_str(".SH SEE ALSO\n");
+    s.push_str("sv(8), runsv(8), runsvdir(8), svlogd(8)\n");
+
+    s
+}
+
+fn subcommand_page(app: &App, sub: &App, page_name: &str) -> String {
+    let name = app.get_name();
+    let mut s = header(page_name, app);
+
+    s.push_str(".SH NAME\n");
+    s.push_str(&format!(
+        "{} \\- {}\n",
+        escape(page_name),
+        escape(sub.get_about().unwrap_or_default())
+    ));
+
+    s.push_str(".SH SYNOPSIS\n");
+    s.push_str(&format!(
+        ".B {} {}\n[\\fIOPTIONS\\fR]",
+        name,
+        escape(sub.get_name())
+    ));
+    if subcommands(sub).next().is_some() {
+        s.push_str(" \\fISUBCOMMAND\\fR");
+    }
+    for arg in visible_args(sub).filter(|arg| arg.is_positional()) {
+        s.push(' ');
+        s.push_str(&positional_usage(arg));
+    }
+    s.push('\n');
+
+    // Subcommands share the version of rsv
+    s.push_str(".SH OPTIONS\n");
+    for arg in visible_args(sub).filter(|arg| arg.get_name() != "version") {
+        s.push_str(&describe_arg(arg));
+    }
+    s.push_str(&format!(
+        ".PP\nSee {}({}) for the global options.\n",
+        name, SECTION
+    ));
+
+    if subcommands(sub).next().is_some() {
+        s.push_str(".SH SUBCOMMANDS\n");
+        for nested in subcommands(sub) {
+            s.push_str(&format!(".TP\n\\fB{}\\fR", escape(nested.get_name())));
+            for arg in visible_args(nested).filter(|arg| arg.is_positional()) {
+                s.push(' ');
+                s.push_str(&positional_usage(arg));
+            }
+            s.push_str(&format!(
+                "\n{}\n",
+                escape(nested.get_about().unwrap_or_default())
+            ));
+        }
+    }
+
+    s.push_str(".SH SEE ALSO\n");
+    s.push_str(&format!("{}({})\n", name, SECTION));
+
+    s
+}
+
+fn header(title: &str, app: &App) -> String {
+    format!(
+        ".TH {} {} \"\" \"{} {}\" \"System Manager's Manual\"\n",
+        escape(&title.to_uppercase()),
+        SECTION,
+        app.get_name(),
+        crate_version!()
+    )
+}
+
+fn subcommands<'a, 'help>(app: &'a App<'help>) -> impl Iterator<Item = &'a App<'help>> {
+    app.get_subcommands()
+        .filter(|sub| !sub.is_set(AppSettings::Hidden))
+}
+
+fn visible_args<'a, 'help>(app: &'a App<'help>) -> impl Iterator<Item = &'a Arg<'help>> {
+    app.get_arguments()
+        .filter(|arg| !arg.is_set(ArgSettings::Hidden))
+}
+
+/// Whether `arg` is one of the global options, not counting --help and --version
+fn is_global(arg: &Arg) -> bool {
+    arg.get_global() && !["help", "version"].contains(&arg.get_name())
+}
+
+/// `<name>`, `[name]` or `[name]...` depending on the arg being required and repeatable
+fn positional_usage(arg: &Arg) -> String {
+    let name = format!("\\fI{}\\fR", escape(arg.get_name()));
+    let usage = if arg.is_set(ArgSettings::Required) {
+        format!("<{}>", name)
+    } else {
+        format!("[{}]", name)
+    };
+
+    if arg.is_set(ArgSettings::MultipleValues) {
+        format!("{}...", usage)
+    } else {
+        usage
+    }
+}
+
+/// A paragraph describing `arg`
+fn describe_arg(arg: &Arg) -> String {
+    let mut s = String::from(".TP\n");
+
+    if arg.is_positional() {
+        s.push_str(&positional_usage(arg));
+    } else {
+        let mut flags = Vec::new();
+        if let Some(short) = arg.get_short() {
+            flags.push(format!("\\fB\\-{}\\fR", short));
+        }
+        if let Some(long) = arg.get_long() {
+            flags.push(format!("\\fB\\-\\-{}\\fR", escape(long)));
+        }
+        s.push_str(&flags.join(", "));
+
+        if arg.is_set(ArgSettings::TakesValue) || arg.get_possible_values().is_some() {
+            s.push_str(&format!("=\\fI{}\\fR", escape(&value_usage(arg))));
+        }
+    }
+    s.push('\n');
+
+    s.push_str(&escape(arg.get_help().unwrap_or_default()));
+
+    let defaults: Vec<String> = arg
+        .get_default_values()
+        .iter()
+        .map(|value| value.to_string_lossy().into_owned())
+        .collect();
+    if !defaults.is_empty() {
+        s.push_str(&format!(" (default: {})", escape(&defaults.join(", "))));
+    }
+    s.push('\n');
+
+    s
+}
+
+/// Possible values of `arg` separated by `|`, or its value name
+fn value_usage(arg: &Arg) -> String {
+    if let Some(values) = arg.get_possible_values() {
+        return values
+            .iter()
+            .map(|value| value.get_name())
+            .collect::<Vec<_>>()
+            .join("|");
+    }
+
+    match arg.get_value_names() {
+        Some(names) => names.join(" "),
+        None => arg.get_name().to_owned(),
+    }
+}
+
+fn exit_status() -> String {
+    String::from(
+        ".SH EXIT STATUS\n\
+         .TP\n0\nThe command succeeded.\n\
+         .TP\n1\nThe command failed, eg. a service wasn't found, a check of \\fBrsv health\\fR \
+         failed or apply didn't converge. A service not reaching the requested state within the \
+         timeout isn't a failure, its status is printed prefixed with timeout, or fkilled if it \
+         was killed.\n\
+         .TP\n2\nThe command line couldn't be parsed.\n",
+    )
+}
+
+fn environment() -> String {
+    let mut s = String::from(".SH ENVIRONMENT\n");
+
+    for (name, description) in [
+        (
+            ENV_RUNSV_DIR,
+            "The directory supervised by runsvdir. Takes precedence over SVDIR.".to_owned(),
+        ),
+        (
+            "SVDIR",
+            "The directory supervised by runsvdir, like for sv(8).".to_owned(),
+        ),
+        (
+            ENV_SERVICE_PATH,
+            "The directory containing all available services.".to_owned(),
+        ),
+        (
+            "SVWAIT",
+            format!(
+                "Seconds to wait for commands to take effect (default: {}).",
+                DEFAULT_TIMEOUT
+            ),
+        ),
+        (
+            "NO_COLOR",
+            "Disables colors if set and not empty, unless --color=always is given.".to_owned(),
+        ),
+        (
+            "XDG_CONFIG_HOME",
+            format!(
+                "Base of the user config dir, $HOME/.config if unset. Contains {}{} and {}{}.",
+                USER_CONF_PATH, DEFAULT_CONF_FILE, USER_CONF_PATH, USER_SERVICES_CONF_FILE
+            ),
+        ),
+        (
+            "SUDO_USER, DOAS_USER",
+            "The user written to the audit log when running as root through sudo or doas."
+                .to_owned(),
+        ),
+    ] {
+        s.push_str(&format!(
+            ".TP\n\\fB{}\\fR\n{}\n",
+            name,
+            escape(&description)
+        ));
+    }
+
+    s.push_str(
+        ".PP\nSVDIR, SVWAIT, NO_COLOR, RSV_RUNSV_DIR and RSV_SERVICE_PATH are passed as \
+         arguments when rsv reruns itself as root, so the sudo or doas policy doesn't have to \
+         keep them. Running as root, config files not owned by root are ignored.\n",
+    );
+    s.push_str(
+        ".PP\nHooks run by \\fBrsv monitor\\fR get RSV_SERVICE, RSV_EVENT, RSV_OLD_STATE, \
+         RSV_NEW_STATE, RSV_OLD_PID, RSV_PID and RSV_UPTIME passed.\n",
+    );
+
+    s
+}
+
+fn files() -> String {
+    let mut s = String::from(".SH FILES\n");
+
+    for (file, description) in [
+        (
+            format!("{}{}", DEFAULT_CONF_PATH, DEFAULT_CONF_FILE),
+            "The system wide config",
+        ),
+        (
+            format!("$XDG_CONFIG_HOME/{}{}", USER_CONF_PATH, DEFAULT_CONF_FILE),
+            "Config of the user, merged into the system wide one",
+        ),
+        (
+            format!(
+                "$XDG_CONFIG_HOME/{}{}",
+                USER_CONF_PATH, USER_SERVICES_CONF_FILE
+            ),
+            "Config used with --user",
+        ),
+        (
+            format!("$HOME/{}", USER_SERVICE_PATH),
+            "Default service dir of user services",
+        ),
+        (AUDIT_LOG.to_owned(), "Default audit log"),
+        (
+            format!("$HOME/{}", USER_AUDIT_LOG),
+            "Default audit log of user services",
+        ),
+        (API_SOCKET.to_owned(), "Default socket of rsv daemon"),
+        (
+            format!("<service>/{}", HEALTH_FILE),
+            "Health checks of a service, in the format of health_checks",
+        ),
+    ] {
+        s.push_str(&format!(
+            ".TP\n\\fI{}\\fR\n{}.\n",
+            escape(&file),
+            escape(description)
+        ));
+    }
+
+    s
+}
+
+fn configuration() -> String {
+    let mut s = String::from(
+        ".SH CONFIGURATION\n\
+         Config files are YAML. Later layers take precedence: built-in defaults, the system wide \
+         config, the user config, the file passed with \\-\\-config, environment variables and \
+         command line flags. All keys are optional.\n",
+    );
+
+    for (key, description) in [
+        ("runsv_dir", "The directory supervised by runsvdir."),
+        (
+            "service_path",
+            "The directory containing all available services.",
+        ),
+        ("timeout", "Seconds to wait for commands to take effect."),
+        (
+            "service_timeouts",
+            "Timeouts of single services, as map of service name to seconds.",
+        ),
+        (
+            "kill_on_timeout",
+            "Kill services which don't stop within the timeout.",
+        ),
+        ("output", "One of auto, sv, table or json."),
+        ("color", "One of auto, always or never."),
+        (
+            "privilege_backend",
+            "One of auto, sudo, doas, run0 or none.",
+        ),
+        (
+            "grants",
+            "Groups allowed to control services, as map of service name to group.",
+        ),
+        (
+            "audit_log",
+            "File changes to services get logged to. Empty to disable.",
+        ),
+        (
+            "health_checks",
+            "Checks a started service has to pass, as map of service name to a list of \
+             tcp: <address>, http: <url>, unix: <path> or command: <command> entries.",
+        ),
+        (
+            "hooks",
+            "Commands run by rsv monitor, as list of on: [<event>...], service: <name> and \
+             command: <command> entries. Events are up, down, finish and pid_change.",
+        ),
+        ("api_socket", "The unix socket rsv daemon listens on."),
+        (
+            "api_allow",
+            "Callers allowed to use the API of rsv daemon, as uids: [...] and gids: [...]. \
+             gids match supplementary groups of the caller as well.",
+        ),
+        (
+            "api_token",
+            "Bearer token required by rsv serve. Services can't be controlled through it without one.",
+        ),
+    ] {
+        s.push_str(&format!(".TP\n\\fB{}\\fR\n{}\n", key, escape(description)));
+    }
+
+    s.push_str(".PP\nExample:\n.PP\n.nf\n.RS\n");
+    for line in [
+        "runsv_dir: /var/service/",
+        "timeout: 10",
+        "health_checks:",
+        "  nginx:",
+        "    - tcp: 127.0.0.1:80",
+    ] {
+        s.push_str(&escape(line));
+        s.push('\n');
+    }
+    s.push_str(".RE\n.fi\n");
+
+    s
+}
+
+/// Escape `text` for roff
+fn escape(text: &str) -> String {
+    let text = text.replace('\\', "\\e").replace('-', "\\-");
+
+    // Lines starting with a dot or quote would be read as requests
+    match text.chars().next() {
+        Some('.') | Some('\'') => format!("\\&{}", text),
+        _ => text,
+    }
+}
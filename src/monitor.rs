@@ -0,0 +1,96 @@
+use serde_derive::{Deserialize, Serialize};
+
+use std::error;
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::SystemTime;
+
+use crate::config::Config;
+use crate::watch::{Event, Transition, Watcher, POLL_INTERVAL};
+
+/// A command run on state changes of services
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Hook {
+    /// Events to run on, all if empty
+    #[serde(default)]
+    pub on: Vec<Event>,
+    /// Service to run on, all if not set
+    #[serde(default)]
+    pub service: Option<String>,
+    /// Shell command to run
+    pub command: String,
+}
+
+impl Hook {
+    fn matches(&self, transition: &Transition) -> bool {
+        (self.on.is_empty() || self.on.contains(&transition.event))
+            && self
+                .service
+                .as_ref()
+                .map_or(true, |service| *service == transition.new.name)
+    }
+
+    /// Start the command with the transition in its environment
+    fn spawn(&self, transition: &Transition) -> std::io::Result<Child> {
+        Command::new("sh")
+            .arg("-c")
+            .arg(&self.command)
+            .stdin(Stdio::null())
+            .env("RSV_SERVICE", &transition.new.name)
+            .env("RSV_EVENT", transition.event.name())
+            .env("RSV_OLD_STATE", &transition.old.state)
+            .env("RSV_NEW_STATE", &transition.new.state)
+            .env("RSV_OLD_PID", transition.old.pid.to_string())
+            .env("RSV_PID", transition.new.pid.to_string())
+            .env("RSV_UPTIME", transition.new.uptime.to_string())
+            .spawn()
+    }
+}
+
+/// Watch all services and run the configured hooks on state changes
+pub fn monitor(config: Config) -> Result<String, Box<dyn error::Error>> {
+    if config.hooks.is_empty() {
+        eprintln!("warning: no hooks configured, only printing state changes");
+    }
+
+    let hooks = config.hooks.clone();
+    let mut watcher = Watcher::new(config);
+    watcher.poll()?;
+
+    // Hooks run in the background and get reaped on later polls
+    let mut running: Vec<Child> = Vec::new();
+
+    loop {
+        thread::sleep(POLL_INTERVAL);
+
+        // Eg. a supervise dir being recreated by runsv
+        let transitions = match watcher.poll() {
+            Ok(transitions) => transitions,
+            Err(err) => {
+                eprintln!("warning: {}", err);
+                continue;
+            }
+        };
+
+        for transition in transitions {
+            println!(
+                "{} {}: {} ({} -> {}, pid {})",
+                humantime::format_rfc3339_seconds(SystemTime::now()),
+                transition.new.name,
+                transition.event.name(),
+                transition.old.state,
+                transition.new.state,
+                transition.new.pid
+            );
+
+            for hook in hooks.iter().filter(|hook| hook.matches(&transition)) {
+                match hook.spawn(&transition) {
+                    Ok(child) => running.push(child),
+                    Err(err) => eprintln!("warning: can't run '{}': {}", hook.command, err),
+                }
+            }
+        }
+
+        running.retain_mut(|child| !matches!(child.try_wait(), Ok(Some(_))));
+    }
+}
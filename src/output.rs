@@ -0,0 +1,143 @@
+use serde_derive::{Deserialize, Serialize};
+
+use std::env;
+
+use crate::sv::status::{FINISH, RUN};
+
+/// Format used to print services
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// A table if stdout is a terminal, sv lines otherwise
+    #[default]
+    Auto,
+    /// Lines like the ones of runit's sv
+    Sv,
+    Table,
+    Json,
+}
+
+impl OutputFormat {
+    /// Resolve `Auto` to the format to use
+    pub fn resolve(&self) -> OutputFormat {
+        match self {
+            OutputFormat::Auto if is_tty() => OutputFormat::Table,
+            OutputFormat::Auto => OutputFormat::Sv,
+            format => *format,
+        }
+    }
+}
+
+/// When to print colored output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorMode {
+    /// Only if stdout is a terminal and NO_COLOR is not set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorMode {
+    pub fn enabled(&self) -> bool {
+        match self {
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+            ColorMode::Auto => is_tty() && env::var_os("NO_COLOR").map_or(true, |v| v.is_empty()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Color {
+    Red,
+    Green,
+    Yellow,
+}
+
+impl Color {
+    fn code(&self) -> &str {
+        match self {
+            Color::Red => "31",
+            Color::Green => "32",
+            Color::Yellow => "33",
+        }
+    }
+}
+
+/// Color used to print the state of a service
+pub fn state_color(state: &str) -> Color {
+    match state {
+        RUN => Color::Green,
+        FINISH => Color::Yellow,
+        _ => Color::Red,
+    }
+}
+
+/// Format seconds for humans, eg. "2h 5m"
+pub fn humanize_secs(secs: u64) -> String {
+    let units = [("d", 86400), ("h", 3600), ("m", 60), ("s", 1)];
+
+    let parts: Vec<String> = units
+        .iter()
+        .scan(secs, |rest, (unit, size)| {
+            let value = *rest / size;
+            *rest %= size;
+            Some((value, unit))
+        })
+        .skip_while(|(value, _)| *value == 0)
+        .take(2)
+        .filter(|(value, _)| *value > 0)
+        .map(|(value, unit)| format!("{}{}", value, unit))
+        .collect();
+
+    if parts.is_empty() {
+        return "0s".to_owned();
+    }
+
+    parts.join(" ")
+}
+
+/// Color `text` if `enabled` is set
+pub fn paint(text: &str, color: Color, enabled: bool) -> String {
+    if !enabled {
+        return text.to_owned();
+    }
+
+    format!("\x1b[{}m{}\x1b[0m", color.code(), text)
+}
+
+/// Whether stdout is a terminal
+pub fn is_tty() -> bool {
+    unsafe { libc::isatty(libc::STDOUT_FILENO) == 1 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn humanizes_zero() {
+        assert_eq!(humanize_secs(0), "0s");
+    }
+
+    #[test]
+    fn humanizes_single_units() {
+        assert_eq!(humanize_secs(59), "59s");
+        assert_eq!(humanize_secs(60), "1m");
+        assert_eq!(humanize_secs(86400), "1d");
+    }
+
+    #[test]
+    fn humanizes_two_largest_units() {
+        assert_eq!(humanize_secs(7500), "2h 5m");
+        assert_eq!(humanize_secs(90061), "1d 1h");
+    }
+
+    #[test]
+    fn skips_zero_units() {
+        assert_eq!(humanize_secs(3601), "1h");
+        assert_eq!(humanize_secs(86460), "1d");
+    }
+}
@@ -0,0 +1,186 @@
+#![cfg_attr(not(feature = "auto_sudo"), allow(dead_code))]
+
+use serde_derive::{Deserialize, Serialize};
+
+use std::env;
+use std::error;
+use std::ffi::OsString;
+use std::io;
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::config::{ENV_RUNSV_DIR, ENV_SERVICE_PATH};
+use crate::sv::error::Error as SvError;
+
+/// Environment variables passed to the privileged rsv. None of them
+/// may point root to files controlled by the invoking user's configs.
+/// They are passed as `--env` flags, since sudo and doas reset the
+/// environment and stock policies refuse to preserve variables
+pub const PRESERVED_ENV: [&str; 5] = [
+    "SVDIR",
+    "SVWAIT",
+    "NO_COLOR",
+    ENV_RUNSV_DIR,
+    ENV_SERVICE_PATH,
+];
+
+/// Program used to gain root privileges
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackendKind {
+    /// The first one found in $PATH
+    #[default]
+    Auto,
+    Sudo,
+    Doas,
+    Run0,
+    /// Never escalate
+    None,
+}
+
+impl BackendKind {
+    /// The backend to use, if any
+    pub fn backend(&self) -> Option<Box<dyn Backend>> {
+        match self {
+            BackendKind::Auto => all_backends()
+                .into_iter()
+                .find(|backend| find_in_path(backend.program()).is_some()),
+            BackendKind::Sudo => Some(Box::new(Sudo)),
+            BackendKind::Doas => Some(Box::new(Doas)),
+            BackendKind::Run0 => Some(Box::new(Run0)),
+            BackendKind::None => None,
+        }
+    }
+}
+
+/// A program running commands as root
+pub trait Backend {
+    fn program(&self) -> &str;
+
+    /// Build the command running `exe` with `args` as root
+    fn command(&self, exe: &Path, args: &[OsString]) -> Command {
+        let mut cmd = Command::new(self.program());
+        cmd.arg("--").arg(exe).args(args);
+        cmd
+    }
+}
+
+pub struct Sudo;
+pub struct Doas;
+pub struct Run0;
+
+impl Backend for Sudo {
+    fn program(&self) -> &str {
+        "sudo"
+    }
+}
+
+impl Backend for Doas {
+    fn program(&self) -> &str {
+        "doas"
+    }
+}
+
+impl Backend for Run0 {
+    fn program(&self) -> &str {
+        "run0"
+    }
+}
+
+fn all_backends() -> Vec<Box<dyn Backend>> {
+    vec![Box::new(Sudo), Box::new(Doas), Box::new(Run0)]
+}
+
+/// Find an executable `program` in $PATH
+fn find_in_path(program: &str) -> Option<PathBuf> {
+    env::split_paths(&env::var_os("PATH")?)
+        .map(|dir| dir.join(program))
+        .find(|path| {
+            path.metadata()
+                .map(|md| md.is_file() && md.permissions().mode() & 0o111 != 0)
+                .unwrap_or(false)
+        })
+}
+
+/// Whether `err` was caused by missing permissions
+pub fn is_access_denied(err: &(dyn error::Error + 'static)) -> bool {
+    if let Some(err) = err.downcast_ref::<io::Error>() {
+        return err.kind() == io::ErrorKind::PermissionDenied;
+    }
+
+    match err.downcast_ref::<SvError>() {
+        Some(SvError::IoError(err)) => err.kind() == io::ErrorKind::PermissionDenied,
+        Some(SvError::SuperviseAccessDenied(_)) => true,
+        _ => false,
+    }
+}
+
+/// Restart rsv as root using `backend` if `result` failed due to
+/// missing permissions. Returns `result` if rsv already runs as root
+pub fn retry_as_root<T>(
+    result: Result<T, Box<dyn error::Error>>,
+    backend: BackendKind,
+) -> Result<T, Box<dyn error::Error>> {
+    #[cfg(feature = "auto_sudo")]
+    if let Err(ref err) = result {
+        if backend != BackendKind::None && is_access_denied(err.as_ref()) {
+            escalate(backend)?;
+        }
+    }
+
+    #[cfg(not(feature = "auto_sudo"))]
+    let _ = backend;
+
+    result
+}
+
+/// Replace the current process with rsv running as root, using the
+/// same arguments and environment. Does nothing if already root
+pub fn escalate(kind: BackendKind) -> Result<(), Box<dyn error::Error>> {
+    if unsafe { libc::geteuid() } == 0 {
+        return Ok(());
+    }
+
+    let backend = kind.backend().ok_or_else(|| {
+        let tried: Vec<String> = all_backends()
+            .iter()
+            .map(|backend| backend.program().to_owned())
+            .collect();
+
+        format!(
+            "Permission denied and no privilege escalation program found (tried {})",
+            tried.join(", ")
+        )
+    })?;
+
+    let exe = env::current_exe()?;
+
+    let mut args = Vec::new();
+    for key in PRESERVED_ENV.iter() {
+        if let Some(value) = env::var_os(key) {
+            let mut var = OsString::from(key);
+            var.push("=");
+            var.push(value);
+            args.push(OsString::from("--env"));
+            args.push(var);
+        }
+    }
+    args.extend(env::args_os().skip(1));
+
+    // Only returns on failure
+    let err = backend.command(&exe, &args).exec();
+    Err(format!("Failed to run {}: {}", backend.program(), err).into())
+}
+
+/// Set a variable passed by `--env` as `KEY=VALUE`
+pub fn restore_env(var: &str) -> Result<(), Box<dyn error::Error>> {
+    let (key, value) = var
+        .split_once('=')
+        .filter(|(key, _)| PRESERVED_ENV.contains(key))
+        .ok_or_else(|| format!("Invalid --env '{}'", var))?;
+
+    env::set_var(key, value);
+    Ok(())
+}
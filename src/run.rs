@@ -1,29 +1,127 @@
 use std::error;
-use std::time::Duration;
+use std::path::Path;
 
-use crate::config::Config;
+use crate::api;
+use crate::completions;
+use crate::config::{Config, Options};
+use crate::exporter;
+use crate::filter::ListFilter;
+use crate::monitor;
+use crate::output::{self, Color, OutputFormat};
+use crate::privilege::{self, BackendKind};
+use crate::state;
 use crate::sv::cmdtype::SvCommandType;
-use crate::sv::service::{Service, ServiceSrc};
-use crate::sv::status::ServiceState;
+use crate::sv::error::{FAIL, OK};
+use crate::sv::info::ServiceInfo;
+use crate::sv::service::{to_json, Service, ServiceSrc};
+use crate::table::service_table;
+use crate::tui;
+use crate::users;
 
 use clap::ArgMatches;
 
 // Run the app
 pub fn run(app: &ArgMatches) -> Result<String, Box<dyn error::Error>> {
-    let config = Config::new()?;
+    // Passed by the unprivileged rsv, before anything reads the environment
+    for var in app.values_of("env").into_iter().flatten() {
+        privilege::restore_env(var)?;
+    }
+
+    let options = Options {
+        user: app.is_present("user"),
+        file: app.value_of("config").map(Path::new),
+        svdir: app.value_of("svdir"),
+        overrides: get_overrides(app),
+        dry_run: app.is_present("dry-run"),
+    };
 
     // Get current subcommand
     let (subcommand, matches) = app
         .subcommand()
         .ok_or_else(|| "No subcommand provided".to_owned())?;
 
-    #[cfg(feature = "auto_sudo")]
-    sudo::escalate_if_needed()?;
+    // Reading the config may need root already, eg. if it contains api_token.
+    // The configured privilege backend is unknown until then
+    let escalation = if options.user {
+        BackendKind::None
+    } else {
+        BackendKind::Auto
+    };
+
+    // The config command has to work with incomplete configs
+    if subcommand == "config" {
+        return privilege::retry_as_root(run_config_command(&options, matches), escalation);
+    }
+
+    // Used by shell completions, which must neither prompt nor escalate
+    if subcommand == "__complete" {
+        return run_complete_command(&options, matches);
+    }
+
+    let config = privilege::retry_as_root(Config::new(&options), escalation)?;
+
+    if app.is_present("verbose") {
+        eprint!("{}", config.describe()?);
+    }
+
+    if app.is_present("save-config") {
+        eprint!("{}", config.save()?);
+    }
+
+    // Only escalate if the command actually needs root
+    let escalation = config.escalation();
+    privilege::retry_as_root(run_command(config, subcommand, matches), escalation)
+}
 
+// Run a subcommand using `config`
+fn run_command(
+    config: Config,
+    subcommand: &str,
+    matches: &ArgMatches,
+) -> Result<String, Box<dyn error::Error>> {
     if subcommand == "list" {
         return run_list_command(config, matches);
     }
 
+    if let "grant" | "revoke" = subcommand {
+        return run_grant_command(config, subcommand, matches);
+    }
+
+    match subcommand {
+        "export" => return state::export(&config),
+        "import" => {
+            let file = matches.value_of("file").ok_or("File arg missing")?;
+            return state::import(&config, Path::new(file));
+        }
+        "apply" => {
+            let file = matches.value_of("file").ok_or("File arg missing")?;
+            return state::apply::apply(&config, Path::new(file));
+        }
+        "health" => return run_health_command(config, matches),
+        "monitor" => return monitor::monitor(config),
+        "tui" => return tui::run(config),
+        "serve" => {
+            let addr = matches.value_of("listen").ok_or("Listen arg missing")?;
+            return api::http::serve(config, addr);
+        }
+        "daemon" => {
+            let socket = matches
+                .value_of("socket")
+                .unwrap_or(&config.api_socket)
+                .to_owned();
+            return api::socket::serve(config, Path::new(&socket));
+        }
+        "exporter" => {
+            let addr = matches.value_of("listen").ok_or("Listen arg missing")?;
+            return exporter::serve(config, addr);
+        }
+        _ => (),
+    }
+
+    if subcommand == "svdirs" {
+        return Ok(config.svdirs().iter().map(|svdir| svdir.format()).collect());
+    }
+
     // New service from App arg
     let service = Service::new(
         matches
@@ -32,13 +130,214 @@ pub fn run(app: &ArgMatches) -> Result<String, Box<dyn error::Error>> {
             .to_owned(),
         config,
         ServiceSrc::RunSvDir,
-    );
+    )?;
 
     // Run the actual command
-    service.run(
-        SvCommandType::from(subcommand),
-        Duration::from_secs(app.value_of("timeout").unwrap_or("7").parse::<u64>()?),
-    )
+    service.run(SvCommandType::from(subcommand))
+}
+
+// Run the grant and revoke subcommands. Grants are saved
+// to the config, since runsv may recreate the supervise files
+fn run_grant_command(
+    config: Config,
+    subcommand: &str,
+    matches: &ArgMatches,
+) -> Result<String, Box<dyn error::Error>> {
+    let mut saved = Config::load_file(config.user)?;
+    saved.dry_run = config.dry_run;
+
+    if subcommand == "grant" && matches.is_present("reapply") {
+        let mut s = String::new();
+        for (name, group) in saved.grants.iter() {
+            let gid = users::group_id(group).ok_or(format!("Unknown group '{}'", group))?;
+            let service = Service::new(name.to_owned(), config.clone(), ServiceSrc::RunSvDir)?;
+            s.push_str(&service.grant(gid)?);
+        }
+
+        return Ok(s);
+    }
+
+    let name = matches.value_of("service").ok_or("Service arg missing")?;
+    let service = Service::new(name.to_owned(), config, ServiceSrc::RunSvDir)?;
+
+    let mut s = if subcommand == "grant" {
+        let group = matches.value_of("group").ok_or("Group arg missing")?;
+        let gid = users::group_id(group).ok_or(format!("Unknown group '{}'", group))?;
+
+        let s = service.grant(gid)?;
+        saved.grants.insert(name.to_owned(), group.to_owned());
+        s
+    } else {
+        let s = service.revoke()?;
+        saved.grants.remove(name);
+        s
+    };
+
+    let result = saved.save()?;
+    if saved.dry_run {
+        s.push_str(&result);
+    }
+
+    Ok(s)
+}
+
+// Run the health subcommand
+fn run_health_command(
+    config: Config,
+    matches: &ArgMatches,
+) -> Result<String, Box<dyn error::Error>> {
+    let colors = config.color.enabled();
+
+    let services: Vec<Service> = match matches.values_of("service") {
+        Some(names) => names
+            .map(|name| Service::new(name.to_owned(), config.clone(), ServiceSrc::RunSvDir))
+            .collect::<Result<_, _>>()?,
+        None => state::enabled_services(&config)?,
+    };
+
+    let mut s = String::new();
+    let mut failed = 0;
+    for service in services {
+        if !service.exists() {
+            return Err(format!("Service '{}' not found", service.uri).into());
+        }
+
+        let checks = service.health_checks()?;
+        if checks.is_empty() {
+            s.push_str(&format!("-: {}: no health checks\n", service.uri));
+            continue;
+        }
+
+        for check in checks {
+            match check.run(config.timeout_for(&service.uri)) {
+                Ok(()) => s.push_str(&format!(
+                    "{}: {}: {}\n",
+                    output::paint(OK, Color::Green, colors),
+                    service.uri,
+                    check
+                )),
+                Err(err) => {
+                    failed += 1;
+                    s.push_str(&format!(
+                        "{}: {}: {}: {}\n",
+                        output::paint(FAIL, Color::Red, colors),
+                        service.uri,
+                        check,
+                        err
+                    ));
+                }
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(format!("{} health checks failed:\n{}", failed, s.trim_end()).into());
+    }
+
+    Ok(s)
+}
+
+// Config values set by command line flags
+fn get_overrides(app: &ArgMatches) -> Vec<(&str, &str)> {
+    let mut overrides = Vec::new();
+
+    for (arg, key) in [
+        ("timeout", "timeout"),
+        ("format", "output"),
+        ("color", "color"),
+    ] {
+        if let Some(value) = app.value_of(arg) {
+            overrides.push((key, value));
+        }
+    }
+
+    if app.is_present("kill") {
+        overrides.push(("kill_on_timeout", "true"));
+    } else if app.is_present("no-kill") {
+        overrides.push(("kill_on_timeout", "false"));
+    }
+
+    overrides
+}
+
+// Print the services completing the argument of a subcommand, one per line
+fn run_complete_command(
+    options: &Options,
+    matches: &ArgMatches,
+) -> Result<String, Box<dyn error::Error>> {
+    let mut config = Config::load(options)?;
+    if let Some(svdir) = options.svdir {
+        config.select_svdir(svdir)?;
+    }
+    config.init_svdir()?;
+
+    let subcommand = matches
+        .value_of("subcommand")
+        .ok_or("Subcommand arg missing")?;
+
+    Ok(completions::candidates(config, subcommand)?
+        .iter()
+        .map(|name| format!("{}\n", name))
+        .collect())
+}
+
+// Run the config subcommand
+pub fn run_config_command(
+    options: &Options,
+    matches: &ArgMatches,
+) -> Result<String, Box<dyn error::Error>> {
+    let (subcommand, matches) = matches
+        .subcommand()
+        .ok_or_else(|| "No subcommand provided".to_owned())?;
+
+    // Values of the config file get changed, never the merged ones
+    if let "set" | "reset" | "path" = subcommand {
+        let mut config = Config::load_file(options.user)?;
+
+        match subcommand {
+            "set" => config.set(
+                matches.value_of("key").ok_or("Key arg missing")?,
+                matches.value_of("value").ok_or("Value arg missing")?,
+            )?,
+            "reset" => config = Config::defaults(options.user)?,
+            _ => return Ok(format!("{}\n", Config::file_path(options.user)?.display())),
+        }
+
+        for problem in config.validate() {
+            eprintln!("warning: {}", problem);
+        }
+
+        config.dry_run = options.dry_run;
+        return config.save();
+    }
+
+    let mut config = Config::load(options)?;
+    if let Some(svdir) = options.svdir {
+        config.select_svdir(svdir)?;
+    }
+
+    // Show what would be used, even if nothing was found. The
+    // configured values are validated as they are
+    if subcommand != "validate" {
+        config.init_svdir().ok();
+    }
+
+    match subcommand {
+        "show" => config.describe(),
+        "get" => Ok(format!(
+            "{}\n",
+            config.get(matches.value_of("key").ok_or("Key arg missing")?)?
+        )),
+        "validate" => {
+            let problems = config.validate();
+            if problems.is_empty() {
+                return Ok("Config is valid\n".to_owned());
+            }
+
+            Err(problems.join("\n").into())
+        }
+        _ => unreachable!(),
+    }
 }
 
 // Run the list subcommand
@@ -46,36 +345,97 @@ pub fn run_list_command(
     config: Config,
     matches: &ArgMatches,
 ) -> Result<String, Box<dyn error::Error>> {
-    Ok(format_services(
-        Service::get_all_services(config)?
-            .into_iter()
-            .filter(|f| match f.read_status() {
-                Ok(status) => {
-                    if matches.is_present("down") && status.state != ServiceState::Down {
-                        return false;
-                    }
-
-                    if matches.is_present("enabled") && f.src == ServiceSrc::ServiceDir {
-                        return false;
-                    }
-
-                    if matches.is_present("disabled") && f.src == ServiceSrc::RunSvDir {
-                        return false;
-                    }
-
-                    if matches.is_present("up") && status.state != ServiceState::Run {
-                        return false;
-                    }
-
-                    true
-                }
-                Err(err) => {
-                    eprintln!("'{}', {}", f.uri, err);
-                    false
+    let output = config.output.resolve();
+    let colors = config.color.enabled();
+    let filter = ListFilter::from_matches(matches)?;
+
+    // Services grouped by the svdir they belong to
+    let mut groups: Vec<(Option<String>, Vec<Service>)> = Vec::new();
+
+    if matches.is_present("all-svdirs") {
+        for svdir in config.svdirs() {
+            let mut config = config.clone();
+            config.runsv_dir = svdir.dir.clone();
+
+            // Services of the source dir don't belong to a specific svdir
+            let services = filter_services(config, &filter)?
+                .into_iter()
+                .filter(|service| service.src == ServiceSrc::RunSvDir)
+                .collect();
+
+            groups.push((Some(svdir.dir), services));
+        }
+    } else {
+        groups.push((None, filter_services(config, &filter)?));
+    }
+
+    if output == OutputFormat::Sv {
+        let mut s = String::new();
+
+        for (svdir, services) in groups {
+            for line in format_services(services).lines() {
+                match svdir {
+                    Some(ref svdir) => s.push_str(&format!("{}: {}\n", svdir, line)),
+                    None => s.push_str(&format!("{}\n", line)),
                 }
-            })
-            .collect(),
-    ))
+            }
+        }
+
+        return Ok(s);
+    }
+
+    let infos: Vec<ServiceInfo> = groups
+        .iter()
+        .flat_map(|(svdir, services)| service_infos(services, svdir.as_deref()))
+        .collect();
+
+    Ok(match output {
+        OutputFormat::Json => format!("{}\n", to_json(&infos)),
+        _ => service_table(&infos, colors),
+    })
+}
+
+// Summaries of `services` which have a readable status
+fn service_infos(services: &[Service], svdir: Option<&str>) -> Vec<ServiceInfo> {
+    services
+        .iter()
+        .filter_map(|service| {
+            let status = service.read_status().ok()?;
+
+            let mut info = ServiceInfo::new(service, &status);
+            info.svdir = svdir.map(|dir| dir.to_owned());
+            Some(info)
+        })
+        .collect()
+}
+
+// Get all services matching the filters of the list subcommand, in the requested order
+fn filter_services(
+    config: Config,
+    filter: &ListFilter,
+) -> Result<Vec<Service>, Box<dyn error::Error>> {
+    let mut services: Vec<(Service, ServiceInfo)> = Vec::new();
+
+    for service in Service::get_all_services(config)? {
+        let status = match service.read_status() {
+            Ok(status) => status,
+            // Retried as root
+            Err(err) if privilege::is_access_denied(&err) => return Err(err.into()),
+            Err(err) => {
+                eprintln!("'{}', {}", service.uri, err);
+                continue;
+            }
+        };
+
+        let info = ServiceInfo::new(&service, &status);
+        if filter.matches(&info) {
+            services.push((service, info));
+        }
+    }
+
+    services.sort_by(|(_, a), (_, b)| filter.compare(a, b));
+
+    Ok(services.into_iter().map(|(service, _)| service).collect())
 }
 
 fn format_services(services: Vec<Service>) -> String {
@@ -0,0 +1,167 @@
+use serde_derive::{Deserialize, Serialize};
+
+use std::error;
+use std::fs;
+use std::path::Path;
+use std::thread::sleep;
+use std::time::{Duration, SystemTime};
+
+use super::{apply_changes, format_plan, Change, Normally, RunState};
+use crate::config::Config;
+use crate::sv::service::{Service, ServiceFile, ServiceSrc};
+use crate::sv::status::{ServiceState, Wants};
+
+/// Desired state of services, converged by `rsv apply`.
+/// Services which aren't listed are left alone
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DesiredState {
+    #[serde(default)]
+    pub services: Vec<DesiredService>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DesiredService {
+    pub name: String,
+    #[serde(default = "enabled_default")]
+    pub enabled: bool,
+    pub state: Option<RunState>,
+    pub normally: Option<Normally>,
+}
+
+fn enabled_default() -> bool {
+    true
+}
+
+/// Converge the services to the state in `file`. Fails
+/// if any of the services didn't reach the desired state
+pub fn apply(config: &Config, file: &Path) -> Result<String, Box<dyn error::Error>> {
+    let state: DesiredState = serde_yaml::from_str(&fs::read_to_string(file)?)?;
+
+    let changes = plan(config, &state)?;
+    if changes.is_empty() {
+        return Ok("Nothing to do\n".to_owned());
+    }
+
+    let mut s = format_plan(&changes);
+    if config.dry_run {
+        return Ok(s);
+    }
+
+    // Newly enabled services can't be controlled before runsv supervises them
+    let mut controls = Vec::new();
+    let mut others = Vec::new();
+    for change in changes {
+        match change {
+            Change::Control(..) => controls.push(change),
+            _ => others.push(change),
+        }
+    }
+
+    // A failed change doesn't keep the others from being applied.
+    // Only fails due to missing permissions, retried as root by the caller
+    let count = others.len() + controls.len();
+    let mut failed = apply_changes(config, &others, &mut s)?;
+
+    for change in controls.iter() {
+        wait_supervised(config, change);
+    }
+    failed += apply_changes(config, &controls, &mut s)?;
+
+    // One-shot services may have finished already
+    let left: Vec<Change> = plan(config, &state)?
+        .into_iter()
+        .filter(|change| !matches!(change, Change::Control(_, RunState::Once)))
+        .collect();
+
+    if !left.is_empty() {
+        return Err(format!(
+            "Not converged:\n{}\n\n{}",
+            format_plan(&left).trim_end(),
+            s.trim_end()
+        )
+        .into());
+    }
+
+    if failed > 0 {
+        return Err(format!("{} of {} changes failed:\n{}", failed, count, s.trim_end()).into());
+    }
+
+    s.push_str(&format!("{} changes applied\n", count));
+    Ok(s)
+}
+
+/// Changes required to reach `state`
+pub fn plan(config: &Config, state: &DesiredState) -> Result<Vec<Change>, Box<dyn error::Error>> {
+    let mut changes = Vec::new();
+
+    for desired in state.services.iter() {
+        let name = &desired.name;
+        let service = Service::new(name.clone(), config.clone(), ServiceSrc::RunSvDir)?;
+        if !service.exists() {
+            return Err(format!("Service '{}' not found", name).into());
+        }
+
+        // Before enabling, so runsv doesn't start normally down services
+        if let Some(normally) = desired.normally {
+            if Normally::of(&service) != normally {
+                changes.push(Change::Normally(name.clone(), normally));
+            }
+        }
+
+        if !desired.enabled {
+            if service.is_enabled() {
+                changes.push(Change::Disable(name.clone()));
+            }
+            continue;
+        }
+
+        if !service.is_enabled() {
+            changes.push(Change::Enable(name.clone()));
+        }
+
+        if let Some(wanted) = desired.state {
+            if !is_in_state(&service, wanted) {
+                changes.push(Change::Control(name.clone(), wanted));
+            }
+        }
+    }
+
+    Ok(changes)
+}
+
+/// Whether `service` is in the `wanted` state. Services
+/// without readable status never are
+fn is_in_state(service: &Service, wanted: RunState) -> bool {
+    if !service.is_enabled() {
+        return false;
+    }
+
+    let status = match service.read_status() {
+        Ok(status) => status,
+        Err(_) => return false,
+    };
+
+    match wanted {
+        RunState::Up => status.state == ServiceState::Run && status.want != Wants::Down,
+        RunState::Down => status.state == ServiceState::Down,
+        RunState::Once => status.state != ServiceState::Down,
+    }
+}
+
+/// Wait until runsv picked up the service of `change`, at most its timeout
+fn wait_supervised(config: &Config, change: &Change) {
+    let name = match change {
+        Change::Control(name, _) => name,
+        _ => return,
+    };
+
+    let service = match Service::new(name.clone(), config.clone(), ServiceSrc::RunSvDir) {
+        Ok(service) => service,
+        Err(_) => return,
+    };
+    let end = SystemTime::now() + config.timeout_for(name);
+
+    while !Path::new(&service.get_file_path(ServiceFile::Ok)).exists() && SystemTime::now() < end {
+        sleep(Duration::from_millis(100));
+    }
+}
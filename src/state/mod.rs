@@ -0,0 +1,254 @@
+use serde_derive::{Deserialize, Serialize};
+
+use std::error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::config::Config;
+use crate::privilege;
+use crate::sv::cmdtype::SvCommandType;
+use crate::sv::service::{Service, ServiceSrc};
+
+pub mod apply;
+
+/// Whether runsv starts a service by itself. See `Service::is_normally_up`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Normally {
+    #[default]
+    Up,
+    Down,
+}
+
+impl Normally {
+    fn of(service: &Service) -> Normally {
+        if service.is_normally_up() {
+            Normally::Up
+        } else {
+            Normally::Down
+        }
+    }
+}
+
+/// State a service should be in
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunState {
+    Up,
+    Down,
+    /// Started once, but not restarted
+    Once,
+}
+
+impl RunState {
+    fn command(&self) -> SvCommandType {
+        match self {
+            RunState::Up => SvCommandType::Up,
+            RunState::Down => SvCommandType::Down,
+            RunState::Once => SvCommandType::Once,
+        }
+    }
+}
+
+/// The enabled services of a host, written by `rsv export`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HostState {
+    /// Name of the runsvdir, eg. "default" for /etc/runit/runsvdir/default
+    #[serde(default)]
+    pub runlevel: String,
+    #[serde(default)]
+    pub services: Vec<ServiceEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServiceEntry {
+    pub name: String,
+    #[serde(default)]
+    pub normally: Normally,
+}
+
+/// A single change made to the services of a host
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    Enable(String),
+    Disable(String),
+    Normally(String, Normally),
+    Control(String, RunState),
+}
+
+impl Change {
+    fn service(&self) -> &str {
+        match self {
+            Change::Enable(name)
+            | Change::Disable(name)
+            | Change::Normally(name, _)
+            | Change::Control(name, _) => name,
+        }
+    }
+
+    fn apply(&self, config: &Config) -> Result<String, Box<dyn error::Error>> {
+        let service = Service::new(
+            self.service().to_owned(),
+            config.clone(),
+            ServiceSrc::RunSvDir,
+        )?;
+
+        Ok(match self {
+            Change::Enable(_) => service.enable(),
+            Change::Disable(_) => service.disable(),
+            Change::Normally(_, normally) => service.set_normally_up(*normally == Normally::Up),
+            Change::Control(_, state) => return service.run(state.command()),
+        }?)
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::Enable(name) => write!(f, "+ enable {}", name),
+            Change::Disable(name) => write!(f, "- disable {}", name),
+            Change::Normally(name, Normally::Up) => write!(f, "~ normally up {}", name),
+            Change::Normally(name, Normally::Down) => write!(f, "~ normally down {}", name),
+            Change::Control(name, RunState::Up) => write!(f, "> start {}", name),
+            Change::Control(name, RunState::Down) => write!(f, "> stop {}", name),
+            Change::Control(name, RunState::Once) => write!(f, "> once {}", name),
+        }
+    }
+}
+
+/// Name of the runsvdir in use
+pub fn runlevel(config: &Config) -> String {
+    let dir =
+        fs::canonicalize(&config.runsv_dir).unwrap_or_else(|_| config.runsv_dir.clone().into());
+
+    dir.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// The enabled services, sorted by name
+pub fn enabled_services(config: &Config) -> Result<Vec<Service>, Box<dyn error::Error>> {
+    let mut services: Vec<Service> = Service::get_all_services(config.clone())?
+        .into_iter()
+        .filter(|service| service.src == ServiceSrc::RunSvDir)
+        .collect();
+
+    services.sort_by(|a, b| a.uri.cmp(&b.uri));
+    Ok(services)
+}
+
+/// The current state of the host as YAML
+pub fn export(config: &Config) -> Result<String, Box<dyn error::Error>> {
+    let state = HostState {
+        runlevel: runlevel(config),
+        services: enabled_services(config)?
+            .iter()
+            .map(|service| ServiceEntry {
+                name: service.uri.clone(),
+                normally: Normally::of(service),
+            })
+            .collect(),
+    };
+
+    Ok(serde_yaml::to_string(&state)?)
+}
+
+/// Enable and disable services to match the state in `file`
+pub fn import(config: &Config, file: &Path) -> Result<String, Box<dyn error::Error>> {
+    let state: HostState = serde_yaml::from_str(&fs::read_to_string(file)?)?;
+
+    let current = runlevel(config);
+    if !state.runlevel.is_empty() && state.runlevel != current {
+        eprintln!(
+            "warning: state of runlevel '{}' gets imported into '{}'",
+            state.runlevel, current
+        );
+    }
+
+    let changes = plan_import(config, &state)?;
+    if changes.is_empty() {
+        return Ok("Nothing to do\n".to_owned());
+    }
+
+    let mut s = format_plan(&changes);
+    if config.dry_run {
+        return Ok(s);
+    }
+
+    let failed = apply_changes(config, &changes, &mut s)?;
+    if failed > 0 {
+        return Err(format!(
+            "{} of {} changes failed:\n{}",
+            failed,
+            changes.len(),
+            s.trim_end()
+        )
+        .into());
+    }
+
+    s.push_str(&format!("{} changes applied\n", changes.len()));
+    Ok(s)
+}
+
+fn plan_import(config: &Config, state: &HostState) -> Result<Vec<Change>, Box<dyn error::Error>> {
+    let mut changes = Vec::new();
+
+    for entry in state.services.iter() {
+        let service = Service::new(entry.name.clone(), config.clone(), ServiceSrc::RunSvDir)?;
+        if !service.exists() {
+            return Err(format!("Service '{}' not found", entry.name).into());
+        }
+
+        // Before enabling, so runsv doesn't start normally down services
+        if Normally::of(&service) != entry.normally {
+            changes.push(Change::Normally(entry.name.clone(), entry.normally));
+        }
+
+        if !service.is_enabled() {
+            changes.push(Change::Enable(entry.name.clone()));
+        }
+    }
+
+    for service in enabled_services(config)? {
+        if !state.services.iter().any(|entry| entry.name == service.uri) {
+            changes.push(Change::Disable(service.uri));
+        }
+    }
+
+    Ok(changes)
+}
+
+/// One line per change
+pub fn format_plan(changes: &[Change]) -> String {
+    changes
+        .iter()
+        .map(|change| format!("{}\n", change))
+        .collect()
+}
+
+/// Apply all `changes`, appending the result of each one to `out`.
+/// Failed changes don't stop the others from being applied, unless
+/// they failed due to missing permissions. Returns the number of
+/// failed changes
+pub fn apply_changes(
+    config: &Config,
+    changes: &[Change],
+    out: &mut String,
+) -> Result<usize, Box<dyn error::Error>> {
+    let mut failed = 0;
+
+    for change in changes {
+        match change.apply(config) {
+            Ok(s) => out.push_str(&s),
+            // Retried as root by the caller
+            Err(err) if privilege::is_access_denied(err.as_ref()) => return Err(err),
+            Err(err) => {
+                out.push_str(&format!("{}: {}\n", change.service(), err));
+                failed += 1;
+            }
+        }
+    }
+
+    Ok(failed)
+}
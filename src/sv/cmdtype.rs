@@ -23,6 +23,8 @@ pub enum SvCommandType {
     Enable,
     Status,
     Restart,
+    Cat,
+    Show,
 }
 
 impl SvCommandType {
@@ -58,6 +60,8 @@ impl From<&str> for SvCommandType {
             "stop" => SvCommandType::Down,
             "status" => SvCommandType::Status,
             "restart" => SvCommandType::Restart,
+            "cat" => SvCommandType::Cat,
+            "show" => SvCommandType::Show,
             "once" => SvCommandType::Once,
             "pause" => SvCommandType::Pause,
             "continue" => SvCommandType::Continue,
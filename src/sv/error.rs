@@ -10,6 +10,7 @@ pub const TIMEOUT: &str = "timeout";
 pub const KILL: &str = "kill";
 
 #[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum Error {
     DirNotFound(String),
     ParsingStatus(String),
@@ -17,6 +18,8 @@ pub enum Error {
     Timeout(),
     ForceKilled(),
     ServiceNotFound(String),
+    InvalidServiceName(String),
+    InvalidHealthChecks(String),
     ServiceNotEnabled(String),
     ServiceAlreadyEnabled(String),
     ServiceAlreadyDisabled(String),
@@ -46,6 +49,8 @@ impl Error {
             Error::Timeout() => format!("{}:", TIMEOUT),
             Error::ServiceNotEnabled(name) => format!("Service '{}' not enabled", name),
             Error::ServiceNotFound(name) => format!("Service '{}' not found", name),
+            Error::InvalidServiceName(name) => format!("Invalid service name '{}'", name),
+            Error::InvalidHealthChecks(s) => format!("Invalid health checks: {}", s),
             Error::ServiceAlreadyEnabled(name) => format!("Service '{}' already enabled", name),
             Error::ServiceAlreadyDisabled(name) => format!("Service '{}' already disabled", name),
             Error::IoError(err) => format!("{}", err),
@@ -0,0 +1,162 @@
+use serde_derive::{Deserialize, Serialize};
+
+use std::fmt;
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::os::unix::net::UnixStream;
+use std::os::unix::process::CommandExt;
+use std::process::{Command, Stdio};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+/// File in the service dir declaring additional health checks
+pub const HEALTH_FILE: &str = "health.conf";
+
+/// Time a single network check may take
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Time between two checks whether a command exited
+const COMMAND_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Time between two runs of the checks while waiting for a service
+pub const CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A check telling whether a running service is actually usable
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthCheck {
+    /// A TCP port accepting connections, eg. 127.0.0.1:80
+    Tcp(String),
+    /// A HTTP GET returning 2xx, eg. http://127.0.0.1:8080/health
+    Http(String),
+    /// A unix socket accepting connections
+    Unix(String),
+    /// A shell command exiting with 0
+    Command(String),
+}
+
+impl HealthCheck {
+    /// Run the check. Commands get killed after `timeout`
+    pub fn run(&self, timeout: Duration) -> Result<(), String> {
+        match self {
+            HealthCheck::Tcp(addr) => connect_tcp(addr).map(|_| ()),
+            HealthCheck::Http(url) => http_get(url),
+            HealthCheck::Unix(path) => UnixStream::connect(path)
+                .map(|_| ())
+                .map_err(|err| err.to_string()),
+            HealthCheck::Command(cmd) => run_command(cmd, timeout),
+        }
+    }
+}
+
+impl fmt::Display for HealthCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HealthCheck::Tcp(addr) => write!(f, "tcp {}", addr),
+            HealthCheck::Http(url) => write!(f, "http {}", url),
+            HealthCheck::Unix(path) => write!(f, "unix {}", path),
+            HealthCheck::Command(cmd) => write!(f, "command {}", cmd),
+        }
+    }
+}
+
+/// Whether all `checks` pass within `timeout`
+pub fn all_pass(checks: &[HealthCheck], timeout: Duration) -> bool {
+    let end = Instant::now() + timeout;
+    checks.iter().all(|check| {
+        check
+            .run(end.saturating_duration_since(Instant::now()))
+            .is_ok()
+    })
+}
+
+/// Run `cmd` using sh, killing it if it doesn't exit within `timeout`
+fn run_command(cmd: &str, timeout: Duration) -> Result<(), String> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(cmd)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        // Allows killing whatever the command started as well
+        .process_group(0)
+        .spawn()
+        .map_err(|err| err.to_string())?;
+
+    let end = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait().map_err(|err| err.to_string())? {
+            if status.success() {
+                return Ok(());
+            }
+
+            return Err(format!("exited with {}", status));
+        }
+
+        if Instant::now() >= end {
+            unsafe { libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL) };
+            child.wait().ok();
+            return Err(format!(
+                "killed after {}",
+                humantime::format_duration(timeout)
+            ));
+        }
+
+        sleep(COMMAND_POLL_INTERVAL);
+    }
+}
+
+fn connect_tcp(addr: &str) -> Result<TcpStream, String> {
+    let addrs = addr.to_socket_addrs().map_err(|err| err.to_string())?;
+
+    let mut last_err = format!("{}: no address", addr);
+    for addr in addrs {
+        match TcpStream::connect_timeout(&addr, CHECK_TIMEOUT) {
+            Ok(stream) => return Ok(stream),
+            Err(err) => last_err = err.to_string(),
+        }
+    }
+
+    Err(last_err)
+}
+
+/// GET `url` using plain HTTP/1.0 and expect a 2xx status
+fn http_get(url: &str) -> Result<(), String> {
+    let rest = url
+        .strip_prefix("http://")
+        .ok_or_else(|| format!("{}: only http:// is supported", url))?;
+
+    let (host, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+
+    let addr = if host.contains(':') {
+        host.to_owned()
+    } else {
+        format!("{}:80", host)
+    };
+
+    let mut stream = connect_tcp(&addr)?;
+    stream.set_read_timeout(Some(CHECK_TIMEOUT)).ok();
+
+    write!(
+        stream,
+        "GET {} HTTP/1.0\r\nHost: {}\r\nConnection: close\r\n\r\n",
+        path, host
+    )
+    .map_err(|err| err.to_string())?;
+
+    let mut head = [0; 12];
+    stream
+        .read_exact(&mut head)
+        .map_err(|err| err.to_string())?;
+
+    // "HTTP/1.1 200"
+    let status = String::from_utf8_lossy(&head[9..12]).into_owned();
+    if status.starts_with('2') {
+        return Ok(());
+    }
+
+    Err(format!("returned {}", status))
+}
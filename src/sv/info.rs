@@ -0,0 +1,40 @@
+use serde_derive::Serialize;
+
+use super::service::Service;
+use super::status::ServiceStatus;
+
+/// Serializable summary of a service and its status
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ServiceInfo {
+    pub name: String,
+    pub enabled: bool,
+    pub state: String,
+    pub pid: i32,
+    /// Seconds since the last state change
+    pub uptime: u64,
+    pub normally_up: bool,
+    pub paused: bool,
+    pub want: String,
+    pub term: bool,
+    /// The runsvdir supervising the service, if
+    /// services of multiple runsvdirs are listed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub svdir: Option<String>,
+}
+
+impl ServiceInfo {
+    pub fn new(service: &Service, status: &ServiceStatus) -> ServiceInfo {
+        ServiceInfo {
+            name: service.uri.clone(),
+            enabled: service.is_enabled(),
+            state: status.state.value().to_owned(),
+            pid: status.pid,
+            uptime: status.time.as_secs(),
+            normally_up: service.is_normally_up(),
+            paused: status.paused,
+            want: status.want.value().to_owned(),
+            term: status.term,
+            svdir: None,
+        }
+    }
+}
@@ -1,5 +1,7 @@
 #![allow(dead_code)]
 pub mod cmdtype;
 pub mod error;
+pub mod health;
+pub mod info;
 pub mod service;
 pub mod status;
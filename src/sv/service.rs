@@ -3,17 +3,23 @@ use super::status::{ServiceState, ServiceStatus};
 
 use std::error;
 use std::ffi::OsString;
-use std::fs;
-use std::io::BufReader;
+use std::fs::{self, File};
+use std::io::{BufReader, ErrorKind};
 use std::io::{Read, Write};
 use std::ops::Add;
-use std::os::unix::fs as ufs;
-use std::path::Path;
+use std::os::unix::fs::{self as ufs, MetadataExt, OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
 use std::thread::sleep;
 use std::time::{Duration, SystemTime};
 
 use super::error::Error as err;
+use super::health::{self, HealthCheck};
+use super::info::ServiceInfo;
+use crate::audit;
 use crate::config::Config;
+use crate::output::{self, OutputFormat};
+use crate::privilege;
+use crate::users;
 
 // A sv command
 #[derive(Debug)]
@@ -29,6 +35,26 @@ pub enum ServiceSrc {
     ServiceDir,
 }
 
+/// Files printed by `cat`, relative
+/// to the service source directory
+pub const DEFINITION_FILES: [&str; 4] = ["run", "finish", "check", "log/run"];
+
+/// Supervise files and the group permissions
+/// required to control the service through them
+const GROUP_CONTROL: [(ServiceFile, u32); 3] = [
+    (ServiceFile::Supervise, 0o050),
+    (ServiceFile::Control, 0o020),
+    (ServiceFile::Ok, 0o020),
+];
+
+/// Status files the group needs to read. They're
+/// world readable by default, so revoke keeps the bits
+const GROUP_READ: [(ServiceFile, u32); 3] = [
+    (ServiceFile::Status, 0o040),
+    (ServiceFile::Stat, 0o040),
+    (ServiceFile::Pid, 0o040),
+];
+
 pub enum ServiceFile {
     // servicedir
     // files
@@ -38,6 +64,7 @@ pub enum ServiceFile {
 
     // supervise
     // files
+    Supervise,
     Pid,
     Control,
     Lock,
@@ -53,6 +80,7 @@ impl ServiceFile {
             ServiceFile::Run => "run",
             ServiceFile::Down => "down",
             ServiceFile::Finish => "finish",
+            ServiceFile::Supervise => "supervise",
             ServiceFile::Check => "supervise/check",
             ServiceFile::Pid => "supervise/pid",
             ServiceFile::Control => "supervise/control",
@@ -64,14 +92,24 @@ impl ServiceFile {
     }
 }
 
+/// Whether `name` can be a service. Names must not
+/// escape the service dirs, since they're joined to them
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && !name.starts_with('.') && !name.contains(['/', '\0'])
+}
+
 impl Service {
     /// Create a new SvCommand object
-    pub fn new(uri: String, settings: Config, src: ServiceSrc) -> Service {
-        Service {
+    pub fn new(uri: String, settings: Config, src: ServiceSrc) -> Result<Service, err> {
+        if !is_valid_name(&uri) {
+            return Err(err::InvalidServiceName(uri));
+        }
+
+        Ok(Service {
             uri,
             config: settings,
             src,
-        }
+        })
     }
 
     pub fn get_file_path(&self, kfile: ServiceFile) -> OsString {
@@ -82,6 +120,16 @@ impl Service {
         OsString::from(&a.as_os_str())
     }
 
+    /// Path of the service in the service source directory
+    pub fn get_source_path(&self) -> PathBuf {
+        Path::new(&self.config.service_path).join(&self.uri)
+    }
+
+    /// Path of the service in the runsvdir
+    pub fn get_enabled_path(&self) -> PathBuf {
+        Path::new(&self.config.runsv_dir).join(&self.uri)
+    }
+
     pub fn get_all_services(config: Config) -> Result<Vec<Self>, Box<dyn error::Error>> {
         let mut services: Vec<Self> = Vec::new();
 
@@ -91,7 +139,7 @@ impl Service {
         ]
         .iter()
         {
-            let dir_entries = match fs::read_dir(&dir) {
+            let dir_entries = match fs::read_dir(dir) {
                 Ok(de) => de,
                 Err(_) => continue,
             };
@@ -103,11 +151,12 @@ impl Service {
 
                 let service_uri = item.unwrap().file_name().into_string().unwrap();
 
-                if services.iter().any(|s| s.uri == service_uri) {
+                // runsvdir ignores dot files as well
+                if !is_valid_name(&service_uri) || services.iter().any(|s| s.uri == service_uri) {
                     continue;
                 }
 
-                services.push(Service::new(service_uri, config.clone(), src.clone()));
+                services.push(Service::new(service_uri, config.clone(), src.clone())?);
             }
         }
 
@@ -115,27 +164,38 @@ impl Service {
     }
 
     /// Run a sv command
-    pub fn run(
-        &self,
-        cmd: SvCommandType,
-        timeout: Duration,
-    ) -> Result<String, Box<dyn error::Error>> {
+    pub fn run(&self, cmd: SvCommandType) -> Result<String, Box<dyn error::Error>> {
         self.check_exists()?;
 
+        let timeout = self.config.timeout_for(&self.uri);
+        let kill_on_timeout = self.config.kill_on_timeout;
+
         Ok(match cmd {
-            SvCommandType::Status => self.status(),
+            SvCommandType::Status => self.report(),
             SvCommandType::Enable => self.enable(),
             SvCommandType::Disable => self.disable(),
-            SvCommandType::Restart => self.restart(timeout),
-            SvCommandType::Up => self.start(timeout, true),
+            SvCommandType::Restart => self.restart(timeout, kill_on_timeout),
+            SvCommandType::Up => self.start(timeout, kill_on_timeout),
+            SvCommandType::Cat => self.cat(),
+            SvCommandType::Show => self.show(),
 
-            _ => self.control(cmd, timeout, true),
+            _ => self.control(cmd, timeout, kill_on_timeout),
         }?)
     }
 
     pub fn start(&self, timeout: Duration, kill_on_timeout: bool) -> Result<String, err> {
         self.check_exists()?;
 
+        if self.config.dry_run {
+            let mut s = String::new();
+            if !self.is_enabled() {
+                s.push_str(&self.enable()?);
+            }
+
+            s.push_str(&self.describe_control(&SvCommandType::Up));
+            return Ok(s);
+        }
+
         if !self.is_enabled() {
             self.enable()?;
             sleep(Duration::from_millis(200));
@@ -150,6 +210,11 @@ impl Service {
         timeout: Duration,
         kill_on_timeout: bool,
     ) -> Result<String, err> {
+        if self.config.dry_run {
+            self.check_enabled()?;
+            return Ok(self.describe_control(&cmd));
+        }
+
         let pre = self.run_control_cmd(cmd, timeout, kill_on_timeout)?;
         Ok(format!("{}: {}", pre, self.status()?))
     }
@@ -160,6 +225,46 @@ impl Service {
         timeout: Duration,
         kill_on_timeout: bool,
     ) -> Result<String, err> {
+        let control = cmd.value().map(|value| value.to_owned());
+        let action = format!("{:?}", cmd).to_lowercase();
+
+        let result = self.write_control_cmd(cmd, timeout, kill_on_timeout);
+        audit::record(
+            &self.config,
+            &self.uri,
+            &action,
+            control.as_deref(),
+            result.as_ref().map(|s| s.as_str()),
+        );
+
+        result
+    }
+
+    fn write_control_cmd(
+        &self,
+        cmd: SvCommandType,
+        timeout: Duration,
+        kill_on_timeout: bool,
+    ) -> Result<String, err> {
+        // Like sv, check access to supervise/ok first. Opening
+        // it must not block if runsv doesn't read from it
+        if let Err(err) = fs::OpenOptions::new()
+            .write(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(self.get_file_path(ServiceFile::Ok))
+        {
+            if err.kind() == ErrorKind::PermissionDenied {
+                return Err(err::SuperviseAccessDenied(self.uri.clone()));
+            }
+        }
+
+        // Fail before starting the service rather
+        // than waiting for checks which can't be run
+        let checks = match cmd {
+            SvCommandType::Up => self.health_checks().map_err(err::InvalidHealthChecks)?,
+            _ => Vec::new(),
+        };
+
         // Write control char into the
         // control file of the service
         let mut file = match fs::OpenOptions::new()
@@ -167,6 +272,9 @@ impl Service {
             .open(self.get_file_path(ServiceFile::Control))
         {
             Ok(f) => f,
+            Err(err) if err.kind() == ErrorKind::PermissionDenied => {
+                return Err(err::SuperviseAccessDenied(self.uri.clone()))
+            }
             Err(err) => return Err(err::IoError(err)),
         };
 
@@ -176,7 +284,7 @@ impl Service {
 
         // Wait for the command to take effect
         // print the result
-        match self.await_command(cmd, timeout, kill_on_timeout) {
+        match self.await_command(cmd, &checks, timeout, kill_on_timeout) {
             Ok(s) => Ok(s),
             Err(err) => match err {
                 err::Timeout() => Ok("timeout".to_owned()),
@@ -186,13 +294,24 @@ impl Service {
         }
     }
 
+    /// What `run_control_cmd` would write, for dry runs
+    fn describe_control(&self, cmd: &SvCommandType) -> String {
+        format!(
+            "Would write '{}' to {}\n",
+            cmd.value().unwrap_or_default(),
+            path_string(self.get_file_path(ServiceFile::Control))
+        )
+    }
+
     fn await_command(
         &self,
         cmd: SvCommandType,
+        checks: &[HealthCheck],
         timeout: Duration,
         kill_on_timeout: bool,
     ) -> Result<String, err> {
         let end = SystemTime::now().add(timeout);
+        let mut next_check = SystemTime::now();
         loop {
             sleep(Duration::from_millis(40));
 
@@ -208,10 +327,18 @@ impl Service {
             let status = self.read_status()?;
             match cmd {
                 SvCommandType::Up => {
+                    // Checks may spawn commands, so
+                    // they aren't run on every poll
                     if (status.pid > 0 && status.state == ServiceState::Run)
                         && ServiceStatus::check_script(self)
+                        && next_check <= SystemTime::now()
                     {
-                        break;
+                        let left = end.duration_since(SystemTime::now()).unwrap_or_default();
+                        if health::all_pass(checks, left) {
+                            break;
+                        }
+
+                        next_check = SystemTime::now().add(health::CHECK_INTERVAL);
                     }
                 }
                 SvCommandType::Down | SvCommandType::Kill | SvCommandType::Exit => {
@@ -227,13 +354,24 @@ impl Service {
         Ok("ok".to_string())
     }
 
-    pub fn restart(&self, timeout: Duration) -> Result<String, err> {
+    pub fn restart(&self, timeout: Duration, kill_on_timeout: bool) -> Result<String, err> {
         let status = self.read_status()?;
+
+        if self.config.dry_run {
+            let mut s = String::new();
+            if status.state != ServiceState::Down {
+                s.push_str(&self.describe_control(&SvCommandType::Down));
+            }
+
+            s.push_str(&self.describe_control(&SvCommandType::Up));
+            return Ok(s);
+        }
+
         if status.state != ServiceState::Down {
-            self.run_control_cmd(SvCommandType::Down, timeout, true)?;
+            self.run_control_cmd(SvCommandType::Down, timeout, kill_on_timeout)?;
         }
 
-        self.run_control_cmd(SvCommandType::Up, timeout, true)?;
+        self.run_control_cmd(SvCommandType::Up, timeout, kill_on_timeout)?;
         sleep(Duration::from_millis(500));
 
         Ok(format!("ok: {}", self.status()?))
@@ -244,10 +382,27 @@ impl Service {
         Ok(self.format_status(self.read_status()?))
     }
 
+    /// Status of the service in the configured output format
+    pub fn report(&self) -> Result<String, err> {
+        self.check_enabled()?;
+        let status = self.read_status()?;
+
+        Ok(match self.config.output {
+            OutputFormat::Json => format!("{}\n", to_json(&ServiceInfo::new(self, &status))),
+            _ => self.format_status(status),
+        })
+    }
+
     pub fn format_status(&self, status: ServiceStatus) -> String {
+        let state = output::paint(
+            status.state.value(),
+            output::state_color(status.state.value()),
+            self.config.color.enabled(),
+        );
+
         let mut fmt: String = format!(
             "{}: {}: (pid {}) {}s",
-            status.state.value(),
+            state,
             self.uri,
             status.pid,
             status.time.as_secs()
@@ -262,16 +417,130 @@ impl Service {
         fmt
     }
 
+    /// Print all files defining the service
+    pub fn cat(&self) -> Result<String, err> {
+        let src = self.get_source_path();
+        let mut s = String::new();
+
+        for file in DEFINITION_FILES.iter() {
+            let path = src.join(file);
+            if !path.is_file() {
+                continue;
+            }
+
+            let content = fs::read_to_string(&path).map_err(err::IoError)?;
+
+            if !s.is_empty() {
+                s.push('\n');
+            }
+
+            s.push_str(&format!("# {}\n", path.display()));
+            s.push_str(&content);
+
+            if !content.ends_with('\n') {
+                s.push('\n');
+            }
+        }
+
+        Ok(s)
+    }
+
+    /// Print the properties of the service
+    /// as key=value pairs
+    pub fn show(&self) -> Result<String, err> {
+        let mut props: Vec<(&str, String)> = vec![
+            ("name", self.uri.clone()),
+            ("source_path", self.get_source_path().display().to_string()),
+            ("enabled", yes_no(self.is_enabled())),
+        ];
+
+        let link = fs::read_link(self.get_enabled_path())
+            .map(|target| target.display().to_string())
+            .unwrap_or_default();
+        props.push(("link_target", link));
+
+        if self.is_enabled() {
+            let status = self.read_status()?;
+            let supervise = self.get_enabled_path().join("supervise");
+
+            props.push(("normally_up", yes_no(status.normallyup)));
+            props.push(("supervise_path", supervise.display().to_string()));
+            props.push((
+                "control_path",
+                path_string(self.get_file_path(ServiceFile::Control)),
+            ));
+            props.push((
+                "status_path",
+                path_string(self.get_file_path(ServiceFile::Status)),
+            ));
+            props.push(("pid", status.pid.to_string()));
+            props.push(("state", status.state.value().to_owned()));
+            props.push(("uptime", status.time.as_secs().to_string()));
+            props.push(("want", status.want.value().to_owned()));
+            props.push(("paused", yes_no(status.paused)));
+            props.push(("term", yes_no(status.term)));
+        } else {
+            props.push(("normally_up", yes_no(self.is_normally_up())));
+        }
+
+        let log_dir = self
+            .log_dir()
+            .map(|dir| dir.display().to_string())
+            .unwrap_or_default();
+        props.push(("log_dir", log_dir));
+
+        Ok(props
+            .into_iter()
+            .map(|(key, value)| format!("{}={}\n", key, value))
+            .collect())
+    }
+
+    /// Try to find the directory svlogd writes
+    /// to by parsing the log/run script
+    pub fn log_dir(&self) -> Option<PathBuf> {
+        let log_src = self.get_source_path().join("log");
+        let script = fs::read_to_string(log_src.join(ServiceFile::Run.to_string())).ok()?;
+
+        script
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.starts_with('#'))
+            .find_map(|line| {
+                let mut args = line
+                    .split_whitespace()
+                    .skip_while(|arg| !arg.ends_with("svlogd"));
+                args.next()?;
+
+                args.filter(|arg| !arg.starts_with('-'))
+                    .last()
+                    .map(|dir| log_src.join(dir))
+            })
+    }
+
     pub fn enable(&self) -> Result<String, err> {
         self.check_exists()?;
         self.check_already_enabled()?;
 
-        if let Err(err) = ufs::symlink(
-            Path::new(&self.config.service_path).join(&self.uri),
-            Path::new(&self.config.runsv_dir).join(&self.uri),
-        ) {
-            return Err(err::IoError(err));
-        };
+        let src = Path::new(&self.config.service_path).join(&self.uri);
+        let dst = Path::new(&self.config.runsv_dir).join(&self.uri);
+
+        if self.config.dry_run {
+            return Ok(format!(
+                "Would create symlink {} -> {}\n",
+                dst.display(),
+                src.display()
+            ));
+        }
+
+        let result = ufs::symlink(src, dst).map_err(err::IoError);
+        audit::record(
+            &self.config,
+            &self.uri,
+            "enable",
+            None,
+            result.as_ref().map(|_| "ok"),
+        );
+        result?;
 
         Ok(format!("Service '{}' enabled successfully\n", self.uri))
     }
@@ -283,19 +552,194 @@ impl Service {
             return Err(err::ServiceAlreadyDisabled(self.uri.clone()));
         }
 
-        if let Err(err) = fs::remove_file(Path::new(&self.config.runsv_dir).join(&self.uri)) {
-            return Err(err::IoError(err));
+        let link = Path::new(&self.config.runsv_dir).join(&self.uri);
+
+        if self.config.dry_run {
+            return Ok(format!("Would remove symlink {}\n", link.display()));
         }
 
+        let result = fs::remove_file(link).map_err(err::IoError);
+        audit::record(
+            &self.config,
+            &self.uri,
+            "disable",
+            None,
+            result.as_ref().map(|_| "ok"),
+        );
+        result?;
+
         Ok(format!("Service '{}' disabled successfully\n", self.uri))
     }
 
+    /// Create or remove the `down` file of the
+    /// service, which keeps runsv from starting it
+    pub fn set_normally_up(&self, up: bool) -> Result<String, err> {
+        self.check_exists()?;
+
+        let down = self.get_source_path().join(ServiceFile::Down.to_string());
+        let (action, verb) = if up {
+            ("normally-up", "remove")
+        } else {
+            ("normally-down", "create")
+        };
+
+        if self.config.dry_run {
+            return Ok(format!("Would {} {}\n", verb, down.display()));
+        }
+
+        let result = if up {
+            fs::remove_file(&down)
+        } else {
+            File::create(&down).map(|_| ())
+        }
+        .map_err(err::IoError);
+
+        audit::record(
+            &self.config,
+            &self.uri,
+            action,
+            None,
+            result.as_ref().map(|_| "ok"),
+        );
+        result?;
+
+        Ok(format!(
+            "Service '{}' is normally {} now\n",
+            self.uri,
+            if up { "up" } else { "down" }
+        ))
+    }
+
+    /// Health checks of the service from the config and its health file
+    pub fn health_checks(&self) -> Result<Vec<HealthCheck>, String> {
+        let mut checks = self
+            .config
+            .health_checks
+            .get(&self.uri)
+            .cloned()
+            .unwrap_or_default();
+
+        let file = self.get_source_path().join(health::HEALTH_FILE);
+        if file.exists() {
+            let content = fs::read_to_string(&file).map_err(|err| err.to_string())?;
+            let from_file: Vec<HealthCheck> = serde_yaml::from_str(&content)
+                .map_err(|err| format!("{}: {}", file.display(), err))?;
+
+            checks.extend(from_file);
+        }
+
+        Ok(checks)
+    }
+
+    /// Allow the group `gid` to control the service
+    /// by giving it access to the supervise files
+    pub fn grant(&self, gid: u32) -> Result<String, err> {
+        self.check_enabled()?;
+
+        let result = IntoIterator::into_iter(GROUP_CONTROL)
+            .chain(GROUP_READ)
+            .map(|(file, mode)| self.set_group_access(file, gid, mode, true))
+            .collect::<Result<String, err>>();
+        audit::record(
+            &self.config,
+            &self.uri,
+            "grant",
+            None,
+            result.as_ref().map(|_| "ok"),
+        );
+        let s = result?;
+
+        if self.config.dry_run {
+            return Ok(s);
+        }
+
+        Ok(format!("Group access to '{}' granted\n", self.uri))
+    }
+
+    /// Revoke the access of the group to the supervise
+    /// files. They're given back to the owners group
+    pub fn revoke(&self) -> Result<String, err> {
+        self.check_enabled()?;
+
+        let supervise = self.get_file_path(ServiceFile::Supervise);
+        let owner = fs::metadata(&supervise).map_err(err::IoError)?.uid();
+        let gid = users::primary_gid(owner).unwrap_or(0);
+
+        let result = IntoIterator::into_iter(GROUP_CONTROL)
+            .chain(GROUP_READ.map(|(file, _)| (file, 0)))
+            .map(|(file, mode)| self.set_group_access(file, gid, mode, false))
+            .collect::<Result<String, err>>();
+        audit::record(
+            &self.config,
+            &self.uri,
+            "revoke",
+            None,
+            result.as_ref().map(|_| "ok"),
+        );
+        let s = result?;
+
+        if self.config.dry_run {
+            return Ok(s);
+        }
+
+        Ok(format!("Group access to '{}' revoked\n", self.uri))
+    }
+
+    /// Change the group of `file` and add or remove the `mode` bits.
+    /// Files which don't exist (yet) are skipped. Returns what
+    /// would be changed on dry runs
+    fn set_group_access(
+        &self,
+        file: ServiceFile,
+        gid: u32,
+        mode: u32,
+        add: bool,
+    ) -> Result<String, err> {
+        let path = self.get_file_path(file);
+
+        let md = match fs::metadata(&path) {
+            Ok(md) => md,
+            Err(error) if error.kind() == ErrorKind::NotFound => return Ok(String::new()),
+            Err(error) => return Err(err::IoError(error)),
+        };
+
+        let mut perms = md.permissions();
+        if add {
+            perms.set_mode(perms.mode() | mode);
+        } else {
+            perms.set_mode(perms.mode() & !mode);
+        }
+
+        if self.config.dry_run {
+            return Ok(format!(
+                "Would set group of {} to {} and mode to {:o}\n",
+                path_string(path),
+                gid,
+                perms.mode() & 0o7777
+            ));
+        }
+
+        ufs::chown(&path, None, Some(gid)).map_err(err::IoError)?;
+        fs::set_permissions(&path, perms).map_err(err::IoError)?;
+
+        Ok(String::new())
+    }
+
     pub fn exists(&self) -> bool {
         Path::new(&self.config.service_path)
             .join(&self.uri)
             .exists()
     }
 
+    /// Whether the service gets started by runsv.
+    /// See http://smarden.org/runit/runsv.8.html
+    pub fn is_normally_up(&self) -> bool {
+        !self
+            .get_source_path()
+            .join(ServiceFile::Down.to_string())
+            .exists()
+    }
+
     pub fn is_enabled(&self) -> bool {
         Path::new(&self.config.runsv_dir).join(&self.uri).exists()
     }
@@ -308,6 +752,10 @@ impl Service {
         let status_path = self.get_file_path(ServiceFile::Status);
         let f = match fs::OpenOptions::new().read(true).open(&status_path) {
             Ok(file) => file,
+            // supervise/stat and supervise/pid are readable by everyone
+            Err(error) if error.kind() == ErrorKind::PermissionDenied => {
+                return ServiceStatus::from_stat(self).map_err(|_| err::IoError(error));
+            }
             Err(error) => return Err(err::IoError(error)),
         };
 
@@ -319,6 +767,16 @@ impl Service {
         Ok(service)
     }
 
+    /// Status of the service, `None` if it can't be read. Missing permissions
+    /// are an error, so callers retry as root instead of hiding the service
+    pub fn try_read_status(&self) -> Result<Option<ServiceStatus>, err> {
+        match self.read_status() {
+            Ok(status) => Ok(Some(status)),
+            Err(error) if privilege::is_access_denied(&error) => Err(error),
+            Err(_) => Ok(None),
+        }
+    }
+
     fn check_already_enabled(&self) -> Result<(), err> {
         if self.is_enabled() {
             return Err(err::ServiceAlreadyEnabled(self.uri.clone()));
@@ -340,3 +798,40 @@ impl Service {
         Ok(())
     }
 }
+
+/// Serialize `value` as pretty JSON
+pub fn to_json<T: serde::Serialize>(value: &T) -> String {
+    serde_json::to_string_pretty(value).expect("serializable value")
+}
+
+fn yes_no(b: bool) -> String {
+    if b { "yes" } else { "no" }.to_owned()
+}
+
+fn path_string(path: OsString) -> String {
+    Path::new(&path).display().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_service_names() {
+        assert!(is_valid_name("sshd"));
+        assert!(is_valid_name("agetty-tty1"));
+        assert!(is_valid_name("foo.bar"));
+    }
+
+    #[test]
+    fn rejects_names_escaping_service_dirs() {
+        assert!(!is_valid_name(""));
+        assert!(!is_valid_name("."));
+        assert!(!is_valid_name(".."));
+        assert!(!is_valid_name(".hidden"));
+        assert!(!is_valid_name("../etc"));
+        assert!(!is_valid_name("foo/bar"));
+        assert!(!is_valid_name("/etc/passwd"));
+        assert!(!is_valid_name("foo\0"));
+    }
+}
@@ -34,6 +34,16 @@ pub enum Wants {
     Down,
 }
 
+impl Wants {
+    pub fn value(&self) -> &str {
+        match self {
+            Wants::NoWant => "none",
+            Wants::Up => "up",
+            Wants::Down => "down",
+        }
+    }
+}
+
 #[derive(PartialEq, Debug)]
 pub enum ServiceState {
     Down,
@@ -100,6 +110,53 @@ impl ServiceStatus {
         })
     }
 
+    /// Read the less detailed, human readable status from supervise/stat
+    /// and supervise/pid. Used if supervise/status is not readable
+    pub fn from_stat(service: &Service) -> Result<ServiceStatus, Error> {
+        let stat_path = service.get_file_path(ServiceFile::Stat);
+        let stat = fs::read_to_string(&stat_path).map_err(Error::IoError)?;
+
+        // eg. "run, want down, got TERM"
+        let mut parts = stat.trim().split(", ");
+        let state = match parts.next() {
+            Some(RUN) => ServiceState::Run,
+            Some(DOWN) => ServiceState::Down,
+            Some(FINISH) => ServiceState::Finish,
+            _ => return Err(Error::ParsingStatus(service.uri.clone())),
+        };
+
+        let flags: Vec<&str> = parts.collect();
+        let want = if flags.contains(&"want up") {
+            Wants::Up
+        } else if flags.contains(&"want down") {
+            Wants::Down
+        } else {
+            Wants::NoWant
+        };
+
+        let pid = fs::read_to_string(service.get_file_path(ServiceFile::Pid))
+            .ok()
+            .and_then(|pid| pid.trim().parse().ok())
+            .unwrap_or(0);
+
+        // runsv rewrites supervise/stat on every state change
+        let time = fs::metadata(&stat_path)
+            .and_then(|md| md.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .unwrap_or_default();
+
+        Ok(ServiceStatus {
+            pid,
+            time: Duration::from_secs(time.as_secs()),
+            state,
+            normallyup: ServiceStatus::normallyup(service),
+            paused: flags.contains(&"paused"),
+            want,
+            term: flags.contains(&"got TERM"),
+        })
+    }
+
     /// Check http://smarden.org/runit/runsv.8.html
     fn normallyup(service: &Service) -> bool {
         if let Err(err) = fs::metadata(service.get_file_path(ServiceFile::Down)) {
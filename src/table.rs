@@ -0,0 +1,165 @@
+use crate::output::{self, Color};
+use crate::sv::info::ServiceInfo;
+use crate::sv::status::RUN;
+
+/// Gap between two columns
+const COLUMN_GAP: &str = "  ";
+
+/// A single cell of a table
+#[derive(Debug, Clone)]
+pub struct Cell {
+    pub text: String,
+    pub color: Option<Color>,
+}
+
+impl Cell {
+    pub fn new<S: Into<String>>(text: S) -> Cell {
+        Cell {
+            text: text.into(),
+            color: None,
+        }
+    }
+
+    pub fn colored<S: Into<String>>(text: S, color: Color) -> Cell {
+        Cell {
+            text: text.into(),
+            color: Some(color),
+        }
+    }
+}
+
+/// A table with left aligned columns
+#[derive(Debug, Default)]
+pub struct Table {
+    header: Vec<String>,
+    rows: Vec<Vec<Cell>>,
+}
+
+impl Table {
+    pub fn new(header: &[&str]) -> Table {
+        Table {
+            header: header.iter().map(|h| h.to_string()).collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn add_row(&mut self, row: Vec<Cell>) {
+        self.rows.push(row);
+    }
+
+    /// Render the table. Cells are only colored if `colors` is set
+    pub fn render(&self, colors: bool) -> String {
+        let mut widths: Vec<usize> = self.header.iter().map(|h| h.chars().count()).collect();
+        for row in self.rows.iter() {
+            for (i, cell) in row.iter().enumerate() {
+                widths[i] = widths[i].max(cell.text.chars().count());
+            }
+        }
+
+        let header: Vec<Cell> = self.header.iter().map(Cell::new).collect();
+
+        let mut s = String::new();
+        for row in std::iter::once(&header).chain(self.rows.iter()) {
+            let line: Vec<String> = row
+                .iter()
+                .enumerate()
+                .map(|(i, cell)| {
+                    // Escape codes have no width, pad the plain text
+                    let padding = " ".repeat(widths[i] - cell.text.chars().count());
+                    match cell.color {
+                        Some(color) => output::paint(&cell.text, color, colors) + &padding,
+                        None => cell.text.clone() + &padding,
+                    }
+                })
+                .collect();
+
+            s.push_str(line.join(COLUMN_GAP).trim_end());
+            s.push('\n');
+        }
+
+        s
+    }
+}
+
+/// Render services as table. A svdir column
+/// is added if any of the services has one
+pub fn service_table(infos: &[ServiceInfo], colors: bool) -> String {
+    let with_svdir = infos.iter().any(|info| info.svdir.is_some());
+
+    let mut header = vec!["NAME", "ENABLED", "STATE", "PID", "UPTIME", "FLAGS"];
+    if with_svdir {
+        header.insert(0, "SVDIR");
+    }
+
+    let mut table = Table::new(&header);
+
+    for info in infos {
+        let mut row = vec![Cell::new(info.name.as_str())];
+
+        if info.enabled {
+            row.push(Cell::new("yes"));
+            row.push(Cell::colored(
+                info.state.as_str(),
+                output::state_color(&info.state),
+            ));
+            row.push(Cell::new(pid_string(info.pid)));
+            row.push(Cell::new(output::humanize_secs(info.uptime)));
+        } else {
+            // Disabled services have no state
+            row.push(Cell::new("no"));
+            row.extend((0..3).map(|_| Cell::new("-")));
+        }
+
+        let (flags, warn) = flags(info);
+        row.push(if warn {
+            Cell::colored(flags, Color::Yellow)
+        } else {
+            Cell::new(flags)
+        });
+
+        if with_svdir {
+            row.insert(0, Cell::new(info.svdir.clone().unwrap_or_default()));
+        }
+
+        table.add_row(row);
+    }
+
+    table.render(colors)
+}
+
+fn pid_string(pid: i32) -> String {
+    if pid > 0 {
+        pid.to_string()
+    } else {
+        "-".to_owned()
+    }
+}
+
+/// Flags of a service like the ones sv prints and
+/// whether any of them should be highlighted as warning
+fn flags(info: &ServiceInfo) -> (String, bool) {
+    let mut flags: Vec<&str> = Vec::new();
+    let mut warn = false;
+
+    if !info.normally_up {
+        flags.push("normally down");
+    }
+
+    if info.enabled && info.state == RUN {
+        for (set, flag) in [
+            (info.paused, "paused"),
+            (info.want == "down", "want down"),
+            (info.term, "got TERM"),
+        ] {
+            if set {
+                flags.push(flag);
+                warn = true;
+            }
+        }
+    } else if info.enabled && info.want == "up" {
+        flags.push("want up");
+        warn = true;
+    }
+
+    (flags.join(", "), warn)
+}
@@ -0,0 +1,419 @@
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use crossterm::style::{self, Attribute, Print, SetAttribute, SetForegroundColor};
+use crossterm::terminal::{
+    self, disable_raw_mode, enable_raw_mode, Clear, ClearType, EnterAlternateScreen,
+    LeaveAlternateScreen,
+};
+use crossterm::{execute, queue};
+
+use std::error;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Stdout, Write};
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+use crate::config::Config;
+use crate::filter::SortBy;
+use crate::output::{self, Color, ColorMode};
+use crate::sv::cmdtype::SvCommandType;
+use crate::sv::info::ServiceInfo;
+use crate::sv::service::{Service, ServiceSrc};
+use crate::sv::status::RUN;
+use crate::watch::POLL_INTERVAL;
+
+/// Width of the details pane
+const DETAILS_WIDTH: u16 = 36;
+/// Column of the state in the service list
+const STATE_COLUMN: u16 = 34;
+/// Bytes read from the end of a log to show its last lines
+const LOG_TAIL_BYTES: u64 = 64 * 1024;
+
+const HELP: &str = "q quit  / filter  s sort  u start  d stop  r restart  e enable  x disable";
+
+/// Restores the terminal when dropped, even on errors
+struct Screen;
+
+impl Screen {
+    fn enter() -> io::Result<Screen> {
+        enable_raw_mode()?;
+        execute!(io::stdout(), EnterAlternateScreen, Hide)?;
+        Ok(Screen)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        execute!(io::stdout(), Show, LeaveAlternateScreen).ok();
+        disable_raw_mode().ok();
+    }
+}
+
+struct App {
+    config: Config,
+    services: Vec<ServiceInfo>,
+    selected: usize,
+    filter: String,
+    editing_filter: bool,
+    sort: SortBy,
+    message: String,
+    /// Why the last refresh couldn't read all services
+    error: Option<String>,
+    colors: bool,
+    /// Messages of control commands running in the background
+    results: Receiver<String>,
+    sender: Sender<String>,
+}
+
+/// Run the interactive service browser until the user quits
+pub fn run(mut config: Config) -> Result<String, Box<dyn error::Error>> {
+    let colors = config.color.enabled();
+
+    // Messages of services get shown in the status line
+    config.color = ColorMode::Never;
+
+    let (sender, results) = mpsc::channel();
+    let mut app = App {
+        colors,
+        config,
+        services: Vec::new(),
+        selected: 0,
+        filter: String::new(),
+        editing_filter: false,
+        sort: SortBy::Name,
+        message: String::new(),
+        error: None,
+        results,
+        sender,
+    };
+    app.refresh();
+
+    let _screen = Screen::enter()?;
+    let mut stdout = io::stdout();
+
+    loop {
+        app.draw(&mut stdout)?;
+
+        if let Ok(message) = app.results.try_recv() {
+            app.message = message;
+            app.refresh();
+        }
+
+        if !event::poll(POLL_INTERVAL)? {
+            app.refresh();
+            continue;
+        }
+
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press && !app.handle_key(key) {
+                return Ok(String::new());
+            }
+        }
+    }
+}
+
+impl App {
+    /// Read the states of all services again. Services
+    /// which can't be read are reported in the status line
+    fn refresh(&mut self) {
+        let all = match Service::get_all_services(self.config.clone()) {
+            Ok(all) => all,
+            Err(err) => {
+                self.error = Some(err.to_string());
+                return;
+            }
+        };
+
+        let mut services = Vec::new();
+        let mut errors = Vec::new();
+        for service in all {
+            match service.try_read_status() {
+                Ok(Some(status)) => services.push(ServiceInfo::new(&service, &status)),
+                Ok(None) => (),
+                Err(err) => errors.push(format!("{}: {}", service.uri, err)),
+            }
+        }
+
+        services.sort_by(|a, b| self.sort.compare(a, b));
+        self.services = services;
+
+        self.error = match errors.len() {
+            0 => None,
+            1 => errors.pop(),
+            n => Some(format!("{} services unreadable, eg. {}", n, errors[0])),
+        };
+
+        // The list may have shrunk
+        self.selected = self.selected.min(self.visible().len().saturating_sub(1));
+    }
+
+    /// Services matching the filter
+    fn visible(&self) -> Vec<&ServiceInfo> {
+        self.services
+            .iter()
+            .filter(|info| info.name.contains(&self.filter))
+            .collect()
+    }
+
+    fn selected(&self) -> Option<&ServiceInfo> {
+        self.visible().get(self.selected).copied()
+    }
+
+    /// Handle a key press. Returns false to quit
+    fn handle_key(&mut self, key: KeyEvent) -> bool {
+        if self.editing_filter {
+            match key.code {
+                KeyCode::Enter | KeyCode::Esc => self.editing_filter = false,
+                KeyCode::Backspace => {
+                    self.filter.pop();
+                }
+                KeyCode::Char(c) => self.filter.push(c),
+                _ => (),
+            }
+
+            self.selected = 0;
+            return true;
+        }
+
+        let count = self.visible().len();
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = (self.selected + 1).min(count.saturating_sub(1))
+            }
+            KeyCode::Char('/') => {
+                self.editing_filter = true;
+                self.filter.clear();
+            }
+            KeyCode::Char('s') => self.sort = self.sort.next(),
+            KeyCode::Char('u') => self.control(SvCommandType::Up),
+            KeyCode::Char('d') => self.control(SvCommandType::Down),
+            KeyCode::Char('r') => self.control(SvCommandType::Restart),
+            KeyCode::Char('e') => self.control(SvCommandType::Enable),
+            KeyCode::Char('x') => self.control(SvCommandType::Disable),
+            _ => return true,
+        }
+
+        self.refresh();
+        true
+    }
+
+    /// Run `cmd` on the selected service in the background,
+    /// since waiting for it may take up to the timeout
+    fn control(&mut self, cmd: SvCommandType) {
+        let name = match self.selected() {
+            Some(info) => info.name.clone(),
+            None => return,
+        };
+
+        self.message = format!("{}: {}...", name, format!("{:?}", cmd).to_lowercase());
+
+        let config = self.config.clone();
+        let sender = self.sender.clone();
+        thread::spawn(move || {
+            let result = Service::new(name.clone(), config, ServiceSrc::RunSvDir)
+                .map_err(|err| err.into())
+                .and_then(|service| service.run(cmd));
+
+            let message = match result {
+                Ok(s) => s.lines().last().unwrap_or_default().to_owned(),
+                Err(err) => format!("{}: {}", name, err),
+            };
+            sender.send(message).ok();
+        });
+    }
+
+    fn draw(&self, out: &mut Stdout) -> io::Result<()> {
+        let (width, height) = terminal::size()?;
+        let visible = self.visible();
+
+        queue!(out, Clear(ClearType::All))?;
+
+        let mut title = format!(
+            "rsv  {} services  sort: {}",
+            visible.len(),
+            self.sort.name()
+        );
+        if self.editing_filter || !self.filter.is_empty() {
+            title.push_str(&format!("  filter: {}", self.filter));
+        }
+        put(out, 0, 0, width, &title, None)?;
+
+        // The list takes the upper half, the details and the log the lower one
+        let list_height = height.saturating_sub(3) / 2;
+        let pane_top = 2 + list_height;
+        let pane_height = height.saturating_sub(pane_top + 2);
+
+        put(
+            out,
+            0,
+            1,
+            width,
+            &format!(
+                "{:<24} {:<8} {:<7} {:>7} {:>8}",
+                "NAME", "ENABLED", "STATE", "PID", "UPTIME"
+            ),
+            None,
+        )?;
+
+        // Keep the selection visible
+        let offset = (self.selected + 1).saturating_sub(list_height as usize);
+
+        for (i, info) in visible
+            .iter()
+            .enumerate()
+            .skip(offset)
+            .take(list_height as usize)
+        {
+            let row = 2 + (i - offset) as u16;
+            if i == self.selected {
+                queue!(out, SetAttribute(Attribute::Reverse))?;
+            }
+
+            put(out, 0, row, width, &row_text(info), None)?;
+            put(
+                out,
+                STATE_COLUMN,
+                row,
+                7,
+                &info.state,
+                Some(output::state_color(&info.state)).filter(|_| self.colors),
+            )?;
+
+            queue!(out, SetAttribute(Attribute::Reset))?;
+        }
+
+        let separator = format!(
+            "{:-<w$}+{:-<r$}",
+            "- details ",
+            "- log ",
+            w = DETAILS_WIDTH as usize,
+            r = width.saturating_sub(DETAILS_WIDTH + 1) as usize
+        );
+        put(out, 0, pane_top, width, &separator, None)?;
+
+        if let Some(info) = self.selected() {
+            for (i, line) in details(info).iter().take(pane_height as usize).enumerate() {
+                put(out, 0, pane_top + 1 + i as u16, DETAILS_WIDTH, line, None)?;
+            }
+
+            let log_dir =
+                Service::new(info.name.clone(), self.config.clone(), ServiceSrc::RunSvDir)
+                    .ok()
+                    .and_then(|service| service.log_dir());
+            let log = match log_dir {
+                Some(dir) => {
+                    let file = dir.join("current");
+                    tail(&file, pane_height as usize)
+                        .unwrap_or_else(|err| vec![format!("{}: {}", file.display(), err)])
+                }
+                None => vec!["No svlogd log found".to_owned()],
+            };
+
+            for (i, line) in log.iter().enumerate() {
+                put(
+                    out,
+                    DETAILS_WIDTH + 1,
+                    pane_top + 1 + i as u16,
+                    width.saturating_sub(DETAILS_WIDTH + 1),
+                    line,
+                    None,
+                )?;
+            }
+        }
+
+        let footer = match self.error {
+            _ if !self.message.is_empty() => &self.message,
+            Some(ref error) => error,
+            None => HELP,
+        };
+        put(out, 0, height.saturating_sub(1), width, footer, None)?;
+
+        out.flush()
+    }
+}
+
+fn row_text(info: &ServiceInfo) -> String {
+    let (pid, uptime) = if info.enabled && info.state == RUN {
+        (info.pid.to_string(), output::humanize_secs(info.uptime))
+    } else {
+        ("-".to_owned(), "-".to_owned())
+    };
+
+    format!(
+        "{:<24.24} {:<8} {:<7} {:>7} {:>8}",
+        info.name,
+        if info.enabled { "yes" } else { "no" },
+        info.state,
+        pid,
+        uptime
+    )
+}
+
+fn details(info: &ServiceInfo) -> Vec<String> {
+    vec![
+        format!("name: {}", info.name),
+        format!("enabled: {}", info.enabled),
+        format!("state: {}", info.state),
+        format!("pid: {}", info.pid),
+        format!("uptime: {}", output::humanize_secs(info.uptime)),
+        format!("normally up: {}", info.normally_up),
+        format!("paused: {}", info.paused),
+        format!("want: {}", info.want),
+        format!("got TERM: {}", info.term),
+    ]
+}
+
+/// Print `text` at `x`, `y`, cut to `width` characters
+fn put(
+    out: &mut Stdout,
+    x: u16,
+    y: u16,
+    width: u16,
+    text: &str,
+    color: Option<Color>,
+) -> io::Result<()> {
+    let text: String = text.chars().take(width as usize).collect();
+
+    // Only reset the color, a full reset would end the highlighting of the selected row
+    match color {
+        Some(color) => queue!(
+            out,
+            MoveTo(x, y),
+            SetForegroundColor(term_color(color)),
+            Print(text),
+            SetForegroundColor(style::Color::Reset)
+        ),
+        None => queue!(out, MoveTo(x, y), Print(text)),
+    }
+}
+
+fn term_color(color: Color) -> style::Color {
+    match color {
+        Color::Red => style::Color::DarkRed,
+        Color::Green => style::Color::DarkGreen,
+        Color::Yellow => style::Color::DarkYellow,
+    }
+}
+
+/// The last `lines` lines of `file`
+fn tail(file: &Path, lines: usize) -> io::Result<Vec<String>> {
+    let mut file = File::open(file)?;
+
+    let len = file.metadata()?.len();
+    file.seek(SeekFrom::Start(len.saturating_sub(LOG_TAIL_BYTES)))?;
+
+    let mut buf = Vec::new();
+    file.read_to_end(&mut buf)?;
+
+    let content = String::from_utf8_lossy(&buf);
+    let all: Vec<&str> = content.lines().collect();
+
+    Ok(all[all.len().saturating_sub(lines)..]
+        .iter()
+        .map(|line| line.to_string())
+        .collect())
+}
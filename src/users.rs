@@ -0,0 +1,58 @@
+use std::ffi::{CStr, CString};
+
+/// Resolve the name of the user with `uid`
+pub fn user_name(uid: u32) -> Option<String> {
+    let passwd = unsafe { libc::getpwuid(uid) };
+    if passwd.is_null() {
+        return None;
+    }
+
+    let name = unsafe { CStr::from_ptr((*passwd).pw_name) };
+    Some(name.to_string_lossy().into_owned())
+}
+
+/// Primary group of the user with `uid`
+pub fn primary_gid(uid: u32) -> Option<u32> {
+    let passwd = unsafe { libc::getpwuid(uid) };
+    if passwd.is_null() {
+        return None;
+    }
+
+    Some(unsafe { (*passwd).pw_gid })
+}
+
+/// Primary and supplementary groups of the user with `uid`,
+/// whose primary group is `gid`
+pub fn group_ids(uid: u32, gid: u32) -> Vec<u32> {
+    let name = match user_name(uid).and_then(|name| CString::new(name).ok()) {
+        Some(name) => name,
+        None => return vec![gid],
+    };
+
+    let mut groups: Vec<libc::gid_t> = vec![0; 32];
+    loop {
+        let mut count = groups.len() as libc::c_int;
+        let res =
+            unsafe { libc::getgrouplist(name.as_ptr(), gid, groups.as_mut_ptr(), &mut count) };
+
+        // Fails if there are more groups, setting count to their number
+        if res >= 0 {
+            groups.truncate(count as usize);
+            return groups;
+        }
+
+        groups.resize(groups.len().max(count as usize) * 2, 0);
+    }
+}
+
+/// Resolve the id of the group called `name`
+pub fn group_id(name: &str) -> Option<u32> {
+    let name = CString::new(name).ok()?;
+
+    let group = unsafe { libc::getgrnam(name.as_ptr()) };
+    if group.is_null() {
+        return None;
+    }
+
+    Some(unsafe { (*group).gr_gid })
+}
@@ -0,0 +1,157 @@
+use serde_derive::{Deserialize, Serialize};
+
+use std::collections::BTreeMap;
+use std::error;
+use std::time::Duration;
+
+use crate::config::Config;
+use crate::sv::info::ServiceInfo;
+use crate::sv::service::Service;
+use crate::sv::status::{DOWN, FINISH, RUN};
+
+/// Time between two polls of the service states
+pub const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Kind of a state change of a service
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Event {
+    /// Started running
+    Up,
+    /// Stopped running
+    Down,
+    /// Entered the finish state
+    Finish,
+    /// Restarted between two polls, while wanted up
+    PidChange,
+}
+
+impl Event {
+    pub fn name(&self) -> &str {
+        match self {
+            Event::Up => "up",
+            Event::Down => "down",
+            Event::Finish => "finish",
+            Event::PidChange => "pid_change",
+        }
+    }
+
+    /// Whether the service got started (again)
+    pub fn is_start(&self) -> bool {
+        matches!(self, Event::Up | Event::PidChange)
+    }
+}
+
+/// A state change observed between two polls
+#[derive(Debug, Clone)]
+pub struct Transition {
+    pub event: Event,
+    pub old: ServiceInfo,
+    pub new: ServiceInfo,
+}
+
+/// Polls the states of all services and reports changes
+pub struct Watcher {
+    config: Config,
+    services: BTreeMap<String, ServiceInfo>,
+}
+
+impl Watcher {
+    pub fn new(config: Config) -> Watcher {
+        Watcher {
+            config,
+            services: BTreeMap::new(),
+        }
+    }
+
+    /// The states of the last poll
+    pub fn services(&self) -> impl Iterator<Item = &ServiceInfo> {
+        self.services.values()
+    }
+
+    /// Read the states of all services. Changes to the states of
+    /// the previous poll are returned, new services have none
+    pub fn poll(&mut self) -> Result<Vec<Transition>, Box<dyn error::Error>> {
+        let mut services = BTreeMap::new();
+        let mut transitions = Vec::new();
+
+        for service in Service::get_all_services(self.config.clone())? {
+            let status = match service.try_read_status()? {
+                Some(status) => status,
+                None => continue,
+            };
+
+            let info = ServiceInfo::new(&service, &status);
+
+            if let Some(old) = self.services.get(&info.name) {
+                if let Some(event) = event(old, &info) {
+                    transitions.push(Transition {
+                        event,
+                        old: old.clone(),
+                        new: info.clone(),
+                    });
+                }
+            }
+
+            services.insert(info.name.clone(), info);
+        }
+
+        self.services = services;
+        Ok(transitions)
+    }
+}
+
+fn event(old: &ServiceInfo, new: &ServiceInfo) -> Option<Event> {
+    if old.state == new.state {
+        let restarted = old.pid > 0 && new.pid > 0 && old.pid != new.pid;
+        return (restarted && new.want != "down").then_some(Event::PidChange);
+    }
+
+    match new.state.as_str() {
+        RUN => Some(Event::Up),
+        FINISH => Some(Event::Finish),
+        DOWN if old.state == RUN => Some(Event::Down),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(state: &str, pid: i32, want: &str) -> ServiceInfo {
+        ServiceInfo {
+            name: "sshd".to_owned(),
+            enabled: true,
+            state: state.to_owned(),
+            pid,
+            uptime: 0,
+            normally_up: true,
+            paused: false,
+            want: want.to_owned(),
+            term: false,
+            svdir: None,
+        }
+    }
+
+    #[test]
+    fn reports_state_changes() {
+        let run = info(RUN, 100, "up");
+        let down = info(DOWN, 0, "down");
+        let finish = info(FINISH, 0, "up");
+
+        assert_eq!(event(&down, &run), Some(Event::Up));
+        assert_eq!(event(&run, &down), Some(Event::Down));
+        assert_eq!(event(&run, &finish), Some(Event::Finish));
+        assert_eq!(event(&finish, &down), None);
+    }
+
+    #[test]
+    fn reports_restarts_while_wanted_up() {
+        let old = info(RUN, 100, "up");
+
+        assert_eq!(event(&old, &info(RUN, 101, "up")), Some(Event::PidChange));
+        assert_eq!(event(&old, &info(RUN, 101, "down")), None);
+        assert_eq!(event(&old, &info(RUN, 100, "up")), None);
+    }
+}